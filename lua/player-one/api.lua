@@ -37,6 +37,18 @@ function M.play(params)
 	return handle_error(ok, err)
 end
 
+---Play a keystroke sound varied by the key's character class
+---@param key string Typed character, or "<CR>", "<BS>" or "<Space>"
+---@param params PlayerOne.SoundParams Sound parameters
+---@return boolean|nil success Whether the sound was played successfully
+function M.play_key(key, params)
+	if not Config.is_enabled then
+		return
+	end
+	local ok, err = pcall(Utils.play_key, key, params)
+	return handle_error(ok, err)
+end
+
 ---Queue a sound to play after current sounds finish
 ---@param params PlayerOne.SoundParams Sound parameters
 ---@return boolean|nil success Whether the sound was queued successfully
@@ -48,6 +60,122 @@ function M.append(params)
 	return handle_error(ok, err)
 end
 
+---Mix several sounds into one source and play it, allowing overlapping notes and chords
+---@param entries PlayerOne.TimelineEntry[] Timeline entries
+---@return boolean|nil success Whether the timeline was played successfully
+function M.play_timeline(entries)
+	if not Config.is_enabled then
+		return
+	end
+	local ok, err = pcall(Utils.play_timeline, entries)
+	return handle_error(ok, err)
+end
+
+---Mix several sounds into one source and queue it after current sounds
+---@param entries PlayerOne.TimelineEntry[] Timeline entries
+---@return boolean|nil success Whether the timeline was queued successfully
+function M.append_timeline(entries)
+	if not Config.is_enabled then
+		return
+	end
+	local ok, err = pcall(Utils.append_timeline, entries)
+	return handle_error(ok, err)
+end
+
+---Play a melody written in MML (Music Macro Language)
+---@param mml string MML source, e.g. "t120 o4 l8 cdefgab>c"
+---@param opts? PlayerOne.MmlOptions Instruments and playback mode
+---@return boolean|nil success Whether the melody was played successfully
+function M.play_mml(mml, opts)
+	if not Config.is_enabled then
+		return
+	end
+	local ok, err = pcall(Utils.play_mml, mml, opts)
+	return handle_error(ok, err)
+end
+
+---Play a chord, or arpeggiate it when `opts.arp` is set
+---@param root string|number Root note name or MIDI note number
+---@param quality? string Chord quality, e.g. "major", "m7", "sus4"
+---@param params PlayerOne.SoundParams Sound template for every note
+---@param opts? PlayerOne.ChordOptions Arpeggio and playback mode
+---@return boolean|nil success Whether the chord was played successfully
+function M.play_chord(root, quality, params, opts)
+	if not Config.is_enabled then
+		return
+	end
+	local ok, err = pcall(Utils.play_chord, root, quality, params, opts)
+	return handle_error(ok, err)
+end
+
+---Play a Standard MIDI File through the sound engine
+---@param path string Path to a .mid file
+---@param opts? PlayerOne.MidiOptions Channel instruments and playback mode
+---@return boolean|nil success Whether the file was played successfully
+function M.play_midi(path, opts)
+	if not Config.is_enabled then
+		return
+	end
+	local ok, err = pcall(Utils.play_midi, path, opts)
+	return handle_error(ok, err)
+end
+
+---Start a multi-track step sequence
+---@param sequence PlayerOne.Sequence Sequence with tracks and default timing
+---@return integer|nil id Sequence id for `stop_sequence`
+function M.play_sequence(sequence)
+	if not Config.is_enabled then
+		return
+	end
+	local ok, result = pcall(Utils.play_sequence, sequence)
+	if not handle_error(ok, result) then
+		return nil
+	end
+	return result
+end
+
+---Stop a sequence started with `play_sequence`
+---@param id integer Sequence id
+---@return boolean|nil success Whether the stop operation succeeded
+function M.stop_sequence(id)
+	local ok, err = pcall(Utils.stop_sequence, id)
+	return handle_error(ok, err)
+end
+
+---Play a 4 channel ProTracker module (.mod)
+---@param path string Path to a .mod file
+---@param opts? PlayerOne.ModOptions Looping and gain
+---@return integer|nil id Module id for `stop_mod`
+function M.play_mod(path, opts)
+	if not Config.is_enabled then
+		return
+	end
+	local ok, result = pcall(Utils.play_mod, path, opts)
+	if not handle_error(ok, result) then
+		return nil
+	end
+	return result
+end
+
+---Stop a module started with `play_mod`
+---@param id integer Module id
+---@return boolean|nil success Whether the stop operation succeeded
+function M.stop_mod(id)
+	local ok, err = pcall(Utils.stop_mod, id)
+	return handle_error(ok, err)
+end
+
+---Check whether a module started with `play_mod` is still playing
+---@param id integer Module id
+---@return boolean|nil playing Whether the module is still playing
+function M.is_mod_playing(id)
+	local ok, result = pcall(Utils.is_mod_playing, id)
+	if not handle_error(ok, result) then
+		return nil
+	end
+	return result
+end
+
 ---Play a sound and wait for it to complete
 ---@param params PlayerOne.SoundParams Sound parameters
 ---@return boolean|nil success Whether the sound was played successfully
@@ -59,6 +187,40 @@ function M.play_and_wait(params)
 	return handle_error(ok, err)
 end
 
+---Play a sound after a sample-accurate delay
+---@param params PlayerOne.SoundParams Sound parameters
+---@param delay_ms number Delay in milliseconds
+---@return boolean|nil success Whether the sound was scheduled successfully
+function M.play_after(params, delay_ms)
+	if not Config.is_enabled then
+		return
+	end
+	local ok, err = pcall(Utils.play_after, params, delay_ms)
+	return handle_error(ok, err)
+end
+
+---Play a sound at an absolute time on the mixer clock
+---@param params PlayerOne.SoundParams Sound parameters
+---@param time_ms number Mixer clock time in milliseconds, see `M.clock`
+---@return boolean|nil success Whether the sound was scheduled successfully
+function M.play_at_time(params, time_ms)
+	if not Config.is_enabled then
+		return
+	end
+	local ok, err = pcall(Utils.play_at_time, params, time_ms)
+	return handle_error(ok, err)
+end
+
+---Get the current mixer clock in milliseconds
+---@return number|nil time_ms Mixer clock time, or nil on error
+function M.clock()
+	local ok, result = pcall(Utils.clock)
+	if not handle_error(ok, result) then
+		return nil
+	end
+	return result
+end
+
 ---Stop all currently playing sounds
 ---@return boolean|nil success Whether the stop operation succeeded
 function M.stop()
@@ -69,6 +231,146 @@ function M.stop()
 	return handle_error(ok, err)
 end
 
+---Convert a note name or MIDI note number to a frequency
+---@param note string|number Note name like "C#4" or "A4+15c", or a MIDI note number
+---@return number freq Frequency in Hz
+function M.note_to_freq(note)
+	return Utils.note_to_freq(note)
+end
+
+---Convert a frequency to the nearest note name
+---@param freq number Frequency in Hz
+---@return string note Nearest note name, e.g. "A4"
+---@return number cents Offset from the nearest note in cents
+function M.freq_to_note(freq)
+	return Utils.freq_to_note(freq)
+end
+
+---Export a plain sfxr sound as jsfxr JSON, which sfxr.me can open
+---@param params PlayerOne.SoundParams|string Sound parameters
+---@return string|nil json JSON parameters
+function M.to_json(params)
+	local ok, result = pcall(Utils.to_json, params)
+	if not handle_error(ok, result) then
+		return nil
+	end
+	return result
+end
+
+---List the problems strict mode would reject a sound table for
+---@param params PlayerOne.SoundParams Sound parameters
+---@return PlayerOne.ValidationIssue[]|nil issues Empty when the table is valid
+function M.validate(params)
+	local ok, result = pcall(Utils.validate, params)
+	if not handle_error(ok, result) then
+		return nil
+	end
+	return result
+end
+
+---Convert a sound to a params table in the units `play` accepts: Hz, seconds and percent
+---@param params PlayerOne.SoundParams|string Sound parameters, JSON, bfxr string or share link
+---@return PlayerOne.SoundParams|nil sound Equivalent params table
+function M.describe(params)
+	local ok, result = pcall(Utils.describe, params)
+	if not handle_error(ok, result) then
+		return nil
+	end
+	return result
+end
+
+---Export a randomised built-in sfxr sound as jsfxr JSON
+---@param name string Preset name: "pickup", "laser", "explosion", "powerup", "hit", "jump" or "blip"
+---@param seed? integer Seed for the randomisation, random when nil
+---@return string|nil json JSON parameters
+function M.preset_to_json(name, seed)
+	local ok, result = pcall(Utils.preset_to_json, name, seed)
+	if not handle_error(ok, result) then
+		return nil
+	end
+	return result
+end
+
+---Encode a plain sfxr sound as an sfxr.me share link
+---@param params PlayerOne.SoundParams|string Sound parameters
+---@return string|nil link Share link
+function M.to_share_link(params)
+	local ok, result = pcall(Utils.to_share_link, params)
+	if not handle_error(ok, result) then
+		return nil
+	end
+	return result
+end
+
+---Load a sound from one of sfxr's .sfs settings files
+---@param path string Path to the .sfs file
+---@return string|nil sound The sound as jsfxr JSON
+function M.load_sfs(path)
+	local ok, result = pcall(Utils.load_sfs, path)
+	if not handle_error(ok, result) then
+		return nil
+	end
+	return result
+end
+
+---Save a plain sfxr sound as an .sfs settings file
+---@param path string Path to write
+---@param params PlayerOne.SoundParams|string Sound parameters
+---@return boolean|nil success Whether the file was written
+function M.save_sfs(path, params)
+	local ok, err = pcall(Utils.save_sfs, path, params)
+	return handle_error(ok, err)
+end
+
+---Give every sound played with `play` the next note of a melody in a key and chord progression
+---@param opts? PlayerOne.MusicalTypingOptions|false Melody settings, nil or false turns it off
+---@return boolean|nil success Whether the setting was applied
+function M.set_musical_typing(opts)
+	local ok, err = pcall(Utils.set_musical_typing, opts)
+	return handle_error(ok, err)
+end
+
+---Load a Scala tuning used for note names and sequencer notes
+---@param scl string Path to a .scl scale file
+---@param kbm? string Path to a .kbm keyboard mapping file
+---@return boolean|nil success Whether the tuning was loaded
+function M.load_tuning(scl, kbm)
+	local ok, err = pcall(Utils.load_tuning, scl, kbm)
+	return handle_error(ok, err)
+end
+
+---Set the reference pitch, A4 unless a keyboard mapping names another key
+---@param freq number Reference frequency in Hz
+---@return boolean|nil success Whether the reference pitch was applied
+function M.set_a4(freq)
+	local ok, err = pcall(Utils.set_a4, freq)
+	return handle_error(ok, err)
+end
+
+---Return to 12-tone equal temperament with A4 at 440 Hz
+---@return boolean|nil success Whether the tuning was reset
+function M.reset_tuning()
+	local ok, err = pcall(Utils.reset_tuning)
+	return handle_error(ok, err)
+end
+
+---Transpose all subsequently played and queued sounds
+---@param semitones number Transpose amount in semitones, e.g. 12 for one octave up
+---@param cents? number Additional fine tuning in cents
+---@return boolean|nil success Whether the transpose was applied
+function M.set_transpose(semitones, cents)
+	local ok, err = pcall(Utils.set_transpose, semitones, cents)
+	return handle_error(ok, err)
+end
+
+---Scale the timing of all subsequently played and queued sounds
+---@param factor number Duration multiplier, e.g. 0.5 makes sounds twice as snappy
+---@return boolean|nil success Whether the time scale was applied
+function M.set_time_scale(factor)
+	local ok, err = pcall(Utils.set_time_scale, factor)
+	return handle_error(ok, err)
+end
+
 ---Load a sound theme
 ---@param theme string|PlayerOne.Theme|nil Theme name or custom theme table
 ---@return boolean|nil success Whether the theme was loaded successfully
@@ -32,12 +32,13 @@ local defaults = {
 			from_env = true,
 		},
 	},
+	strict = false,
 	debug = false,
 }
 
 -- Available built-in themes
 ---@type string[] List of available theme names
-M.themes = { "chiptune", "synth", "crystal" }
+M.themes = { "chiptune", "synth", "crystal", "keyboard" }
 
 --- Check if a sound event is enabled for the current theme
 ---@param event string The event name to check
@@ -8,8 +8,37 @@ local Config = require("player-one.config")
 ---@field setup fun(options?: PlayerOne.Config): PlayerOne
 ---@field play fun(sound: PlayerOne.SoundParams)
 ---@field play_and_wait fun(sound: PlayerOne.SoundParams)
+---@field play_key fun(key: string, sound: PlayerOne.SoundParams)
 ---@field append fun(sound: PlayerOne.SoundParams)
+---@field play_timeline fun(entries: PlayerOne.TimelineEntry[])
+---@field append_timeline fun(entries: PlayerOne.TimelineEntry[])
+---@field play_after fun(sound: PlayerOne.SoundParams, delay_ms: number)
+---@field play_at_time fun(sound: PlayerOne.SoundParams, time_ms: number)
+---@field clock fun(): number
+---@field play_mml fun(mml: string, opts?: PlayerOne.MmlOptions)
+---@field play_midi fun(path: string, opts?: PlayerOne.MidiOptions)
+---@field play_chord fun(root: string|number, quality?: string, sound: PlayerOne.SoundParams, opts?: PlayerOne.ChordOptions)
+---@field play_sequence fun(sequence: PlayerOne.Sequence): integer|nil
+---@field stop_sequence fun(id: integer)
+---@field play_mod fun(path: string, opts?: PlayerOne.ModOptions): integer|nil
+---@field stop_mod fun(id: integer)
+---@field is_mod_playing fun(id: integer): boolean|nil
 ---@field stop fun()
+---@field note_to_freq fun(note: string|number): number
+---@field freq_to_note fun(freq: number): string, number
+---@field validate fun(sound: PlayerOne.SoundParams): PlayerOne.ValidationIssue[]|nil
+---@field describe fun(sound: PlayerOne.SoundParams|string): PlayerOne.SoundParams|nil
+---@field to_json fun(sound: PlayerOne.SoundParams|string): string|nil
+---@field preset_to_json fun(name: string, seed?: integer): string|nil
+---@field to_share_link fun(sound: PlayerOne.SoundParams|string): string|nil
+---@field load_sfs fun(path: string): string|nil
+---@field save_sfs fun(path: string, sound: PlayerOne.SoundParams|string)
+---@field set_musical_typing fun(opts?: PlayerOne.MusicalTypingOptions|false)
+---@field load_tuning fun(scl: string, kbm?: string)
+---@field set_a4 fun(freq: number)
+---@field reset_tuning fun()
+---@field set_transpose fun(semitones: number, cents?: number)
+---@field set_time_scale fun(factor: number)
 ---@field load_theme fun(theme: string|PlayerOne.Theme)
 ---@field enable fun()
 ---@field disable fun()
@@ -10,10 +10,10 @@ return {
 	{
 		event = "VimEnter",
 		sound = {
-			{ wave_type = 1, base_freq = 523.25, env_attack = 0.0, env_sustain = 0.001, env_decay = 0.15 },
-			{ wave_type = 1, base_freq = 587.33, env_attack = 0.0, env_sustain = 0.001, env_decay = 0.15 },
-			{ wave_type = 1, base_freq = 659.25, env_attack = 0.0, env_sustain = 0.001, env_decay = 0.15 },
-			{ wave_type = 1, base_freq = 783.99, env_attack = 0.0, env_sustain = 0.001, env_decay = 0.15 },
+			{ engine = "nes", channel = "pulse", duty = 25, base_freq = 523.25, hold = 0.15, envelope = 1 },
+			{ engine = "nes", channel = "pulse", duty = 25, base_freq = 587.33, hold = 0.15, envelope = 1 },
+			{ engine = "nes", channel = "pulse", duty = 25, base_freq = 659.25, hold = 0.15, envelope = 1 },
+			{ engine = "nes", channel = "pulse", duty = 25, base_freq = 783.99, hold = 0.15, envelope = 1 },
 		},
 		callback = function(sound)
 			Utils.append(sound)
@@ -40,28 +40,29 @@ return {
 	{
 		event = "VimLeavePre",
 		sound = {
-			{ wave_type = 1, base_freq = 1046.50, env_attack = 0.0, env_sustain = 0.02, env_decay = 0.1 },
-			{ wave_type = 1, base_freq = 1318.51, env_attack = 0.0, env_sustain = 0.02, env_decay = 0.08 },
+			{ engine = "nes", channel = "pulse", duty = 12.5, base_freq = 1046.50, hold = 0.12 },
+			{ engine = "nes", channel = "pulse", duty = 12.5, base_freq = 1318.51, hold = 0.1 },
 		},
 		callback = "play_and_wait",
 	},
 	{
 		event = "BufWritePost",
 		sound = {
-			{ wave_type = 2, base_freq = 636.7, env_attack = 0.0, env_sustain = 0.001, env_decay = 0.15 },
-			{ wave_type = 2, base_freq = 523.25, env_attack = 0.0, env_sustain = 0.001, env_decay = 0.15 },
+			{ engine = "nes", channel = "triangle", base_freq = 636.7, hold = 0.15 },
+			{ engine = "nes", channel = "triangle", base_freq = 523.25, hold = 0.15 },
 		},
 		callback = "append",
 	},
 	{
 		event = "TextChangedI",
 		sound = { wave_type = 1, base_freq = 760.0, env_attack = 0.0, env_sustain = 0.001, env_decay = 0.05 },
+		callback = "play_key",
 	},
 	{
 		event = "TextYankPost",
 		sound = {
-			{ wave_type = 1, base_freq = 1760.0, env_attack = 0.0, env_sustain = 0.001, env_decay = 0.15 },
-			{ wave_type = 1, base_freq = 2197.0, env_attack = 0.0, env_sustain = 0.001, env_decay = 0.15 },
+			{ engine = "nes", channel = "noise", noise_mode = "short", noise_period = 3, hold = 0.1, envelope = 0 },
+			{ engine = "nes", channel = "noise", noise_mode = "short", noise_period = 2, hold = 0.15, envelope = 1 },
 		},
 		callback = "append",
 	},
@@ -10,33 +10,18 @@ return {
 	{
 		event = "VimEnter",
 		sound = {
-			{
+			-- F6 A6 C7
+			mml = "t150 l8 o6 v15 f v13 a v11 > c",
+			instrument = {
 				wave_type = 0,
-				base_freq = 1396.91,
 				env_attack = 0.0,
-				env_sustain = 0.001,
 				env_decay = 0.2,
 				volume = 0.7,
-			},
-			{
-				wave_type = 0,
-				base_freq = 1760.00,
-				env_attack = 0.0,
-				env_sustain = 0.001,
-				env_decay = 0.18,
-				volume = 0.6,
-			},
-			{
-				wave_type = 0,
-				base_freq = 2093.00,
-				env_attack = 0.0,
-				env_sustain = 0.001,
-				env_decay = 0.15,
-				volume = 0.5,
+				glide = 30,
 			},
 		},
 		callback = function(sound)
-			Utils.append(sound)
+			Utils.play_mml(sound.mml, { instrument = sound.instrument, append = true })
 			vim.defer_fn(function()
 				Config._is_cursormoved_enabled = true
 			end, 1000)
@@ -61,32 +46,18 @@ return {
 	{
 		event = "VimLeavePre",
 		sound = {
-			{
+			-- C7 A6 F6
+			mml = "t150 l8 o7 v15 c < v13 a v11 f",
+			instrument = {
 				wave_type = 0,
-				base_freq = 2093.00,
-				env_attack = 0.0,
-				env_sustain = 0.001,
-				env_decay = 0.15,
-				volume = 0.7,
-			},
-			{
-				wave_type = 0,
-				base_freq = 1760.00,
 				env_attack = 0.05,
-				env_sustain = 0.001,
-				env_decay = 0.18,
-				volume = 0.6,
-			},
-			{
-				wave_type = 0,
-				base_freq = 1396.91,
-				env_attack = 0.1,
-				env_sustain = 0.001,
 				env_decay = 0.2,
-				volume = 0.5,
+				volume = 0.7,
 			},
 		},
-		callback = "play_and_wait",
+		callback = function(sound)
+			Utils.play_mml(sound.mml, { instrument = sound.instrument, wait = true })
+		end,
 	},
 	{
 		event = "BufWritePost",
@@ -106,6 +77,7 @@ return {
 				env_sustain = 0.001,
 				env_decay = 0.12,
 				volume = 0.5,
+				glide = 30,
 			},
 		},
 		callback = "append",
@@ -120,6 +92,7 @@ return {
 			env_decay = 0.05,
 			volume = 0.4,
 		},
+		callback = "play_key",
 	},
 	{
 		event = "TextYankPost",
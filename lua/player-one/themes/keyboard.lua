@@ -0,0 +1,22 @@
+--- @brief Mechanical keyboard theme synthesized from switch models, no recordings
+
+---@type PlayerOne.Theme
+return {
+	{
+		event = "TextChangedI",
+		sound = { engine = "switch", switch = "tactile", stroke = "press", volume = 0.6 },
+		callback = "play_key",
+	},
+	{
+		event = "InsertLeave",
+		sound = { engine = "switch", switch = "tactile", stroke = "release", volume = 0.4 },
+	},
+	{
+		event = "TextYankPost",
+		sound = { engine = "switch", switch = "clicky", stroke = "pair", hold = 0.06, volume = 0.5 },
+	},
+	{
+		event = "BufWritePost",
+		sound = { engine = "switch", switch = "linear", stroke = "pair", base_freq = 700, hold = 0.12, volume = 0.7 },
+	},
+}
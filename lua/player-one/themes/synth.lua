@@ -10,54 +10,18 @@ return {
 	{
 		event = "VimEnter",
 		sound = {
-			{
-				wave_type = 1,
-				base_freq = 277.18,
-				env_attack = 0.0,
-				env_sustain = 0.001,
-				env_decay = 0.15,
-				duty = 0.6,
-				lpf_freq = 4000,
-			},
-			{
-				wave_type = 1,
-				base_freq = 369.99,
-				env_attack = 0.0,
-				env_sustain = 0.001,
-				env_decay = 0.15,
-				duty = 0.6,
-				lpf_freq = 4000,
-			},
-			{
-				wave_type = 1,
-				base_freq = 440.00,
-				env_attack = 0.0,
-				env_sustain = 0.001,
-				env_decay = 0.15,
-				duty = 0.6,
-				lpf_freq = 4000,
-			},
-			{
-				wave_type = 1,
-				base_freq = 554.37,
-				env_attack = 0.0,
-				env_sustain = 0.001,
-				env_decay = 0.15,
-				duty = 0.6,
-				lpf_freq = 4000,
-			},
-			{
+			-- C#4 F#4 A4 C#5 F#5
+			mml = "t99 l16 o4 c+ f+ a > c+ f+",
+			instrument = {
 				wave_type = 1,
-				base_freq = 739.99,
 				env_attack = 0.0,
-				env_sustain = 0.001,
 				env_decay = 0.15,
 				duty = 0.6,
 				lpf_freq = 4000,
 			},
 		},
 		callback = function(sound)
-			Utils.append(sound)
+			Utils.play_mml(sound.mml, { instrument = sound.instrument, append = true })
 			vim.defer_fn(function()
 				Config._is_cursormoved_enabled = true
 			end, 1000)
@@ -85,27 +49,18 @@ return {
 	{
 		event = "VimLeavePre",
 		sound = {
-			{
+			-- A3 D4
+			mml = "t99 l16 o3 a > d",
+			instrument = {
 				wave_type = 1,
-				base_freq = 220.00,
 				env_attack = 0.0,
-				env_sustain = 0.001,
-				env_decay = 0.15,
-				duty = 0.6,
-				lpf_freq = 4000,
-			},
-			{
-				wave_type = 1,
-				base_freq = 293.66,
-				env_attack = 0.0,
-				env_sustain = 0.001,
 				env_decay = 0.15,
 				duty = 0.6,
 				lpf_freq = 4000,
 			},
 		},
 		callback = function(sound)
-			Utils.play_and_wait(sound)
+			Utils.play_mml(sound.mml, { instrument = sound.instrument, wait = true })
 		end,
 	},
 	--- @type PlayerOne.Sound
@@ -145,6 +100,7 @@ return {
 			duty = 0.3,
 			lpf_freq = 2000,
 		},
+		callback = "play_key",
 	},
 	--- @type PlayerOne.Sound
 	{
@@ -4,14 +4,42 @@
 ---| 2 # Sine wave
 ---| 3 # Noise wave
 ---| 4 # Triangle wave
+---| 5 # Pink noise (bfxr)
+---| 6 # Tan wave (bfxr)
+---| 7 # Whistle (bfxr)
+---| 8 # Breaker (bfxr)
+---| 9 # 1-bit noise (bfxr)
+---| 10 # Buzz, periodic 1-bit noise (bfxr)
+
+---@alias PlayerOne.Engine
+---| "sfxr" # Classic sfxr generator using the parameters below
+---| "switch" # Mechanical keyboard switch; base_freq sets the body resonance
+---| "fm" # 2-4 operator FM synthesis; base_freq sets the note
+---| "pluck" # Karplus-Strong plucked string; base_freq sets the note
+---| "nes" # NES APU pulse, triangle or noise channel; base_freq sets the note
+
+---@alias PlayerOne.FmAlgorithm
+---| "stack" # 4 -> 3 -> 2 -> 1
+---| "pairs" # 2 -> 1 and 4 -> 3
+---| "branch" # 2, 3 and 4 all modulate 1
+---| "parallel" # Every operator is heard directly
+
+---@class PlayerOne.FmOperator
+---@field ratio? number Frequency as a multiple of base_freq (default: 1)
+---@field index? number Modulation depth in radians, or output level for carriers (default: 1)
+---@field attack? number Attack time in seconds, at most 60 (default: 0.002)
+---@field decay? number Decay time in seconds, at most 60 (default: 0.3)
+---@field sustain? number Level held after the decay, 0.0-1.0 (default: 0)
+---@field release? number Release time in seconds, at most 60 (default: 0.1)
+---@field feedback? number Self modulation depth in radians (default: 0)
 
 ---@class PlayerOne.SoundParams
 ---@field wave_type? WaveType Wave type (default: 0 square)
----@field base_freq? number Base frequency in Hz (default: 440)
----@field freq_limit? number Minimum frequency during slides in Hz
+---@field base_freq? number|string Base frequency in Hz, note name like "C#4" or "A4+15c", or MIDI note number string like "69"
+---@field freq_limit? number|string Minimum frequency during slides in Hz, note name or MIDI note number string
 ---@field freq_ramp? number Frequency change over time in octaves/sec
 ---@field freq_dramp? number Change in frequency slide in octaves/sec²
----@field duty? number Square wave duty cycle percentage (0-100)
+---@field duty? number Square wave duty cycle percentage (0-100); "nes" pulses snap to 12.5, 25, 50 or 75
 ---@field duty_ramp? number Change in duty cycle %/sec
 ---@field vib_strength? number Vibrato depth ±%
 ---@field vib_speed? number Vibrato frequency in Hz
@@ -29,12 +57,104 @@
 ---@field repeat_speed? number Sound repeat frequency in Hz
 ---@field arp_speed? number Time between arpeggio notes in seconds
 ---@field arp_mod? number Frequency multiplier for arpeggio
+---@field channel? "pulse"|"triangle"|"noise" APU channel for the "nes" engine (default: "pulse")
+---@field noise_mode? "long"|"short" Hiss, or the metallic 93-step periodic noise of the "nes" noise channel (default: "long")
+---@field noise_period? integer "nes" noise period table index 0-15, used instead of base_freq
+---@field level? integer Constant 4-bit volume 0-15 of a "nes" channel (default: 15)
+---@field envelope? integer "nes" envelope period 0-15; the volume falls from 15 every period + 1 240 Hz frames
+---@field wavetable? number[] Single-cycle waveform played instead of wave_type, e.g. 32 4-bit values like the Game Boy wave channel; rescaled to full range
+---@field overtones? integer Harmonics added on top of the wave, 0-10 (bfxr)
+---@field overtone_falloff? number Level lost by each successive overtone, 0.0-1.0 (bfxr)
+---@field bit_crush? number Fraction of the sample rate thrown away, 0.0-1.0 (bfxr)
+---@field bit_crush_sweep? number Change in bit_crush per second (bfxr)
+---@field compression? number Upward compression amount, 0.0-1.0 (bfxr)
 ---@field sound_vol? number Sound-specific volume (0.0-1.0), modulated by master_volume
+---@field glide? number Milliseconds to glide from the previous note's pitch when appended or sequenced; the pluck and switch engines don't glide
+---@field engine? PlayerOne.Engine Synthesis engine (default: "sfxr")
+---@field switch? "linear"|"tactile"|"clicky" Switch model for the "switch" engine (default: "linear")
+---@field stroke? "press"|"release"|"pair" Bottom-out, release, or both for the "switch" engine (default: "press")
+---@field hold? number Seconds between press and release of a "pair" stroke (default: 0.09), before an "fm" note is released (default: 0.2), a "pluck" string is muted (default: 0.6), or a "nes" length counter silences the channel (default: 0.2); at most 60
+---@field algorithm? PlayerOne.FmAlgorithm How "fm" operators modulate each other (default: "stack")
+---@field operators? PlayerOne.FmOperator[] 2-4 operators for the "fm" engine, operator 1 first
+---@field damping? number How quickly a "pluck" string dies away, 0.0-1.0 (default: 0.3)
+---@field brightness? number Treble of a "pluck" string, 0.0 soft to 1.0 picked (default: 0.5)
+---@field pick_position? number Where a "pluck" string is plucked, as a fraction of its length (default: 0.2)
+
+---@class PlayerOne.TimelineEntry
+---@field sound PlayerOne.SoundParams|string Sound to place on the timeline
+---@field at_ms? number Start offset in milliseconds (default: when the previous entry ends)
+---@field gain? number Gain multiplier for this entry (default: 1.0)
+
+---@class PlayerOne.MmlOptions
+---@field instrument? PlayerOne.SoundParams|string Sound template used for every voice
+---@field instruments? (PlayerOne.SoundParams|string)[] Sound template per voice, cycled if there are more voices
+---@field append? boolean Queue the melody after current sounds instead of playing immediately
+---@field wait? boolean Queue the melody and block until it finishes
+
+---@class PlayerOne.MidiOptions
+---@field channels? table<integer, PlayerOne.SoundParams|string> Instrument per MIDI channel (1-16)
+---@field instrument? PlayerOne.SoundParams|string Instrument for channels without their own
+---@field append? boolean Queue the file after current sounds instead of playing immediately
+---@field wait? boolean Queue the file and block until it finishes
+
+---@class PlayerOne.SanitizeOptions
+---@field apply_master_volume? boolean Scale the volume by the master volume (default: true)
+
+---@alias PlayerOne.ArpPattern
+---| "up" # Lowest to highest
+---| "down" # Highest to lowest
+---| "updown" # Up then back down
+---| "random" # Shuffled
+
+---@class PlayerOne.ChordOptions
+---@field arp? PlayerOne.ArpPattern Arpeggiate instead of playing the notes together
+---@field rate? number Milliseconds between arpeggio notes (default: 100)
+---@field octaves? integer Octaves the arpeggio spans, 1 to 8 (default: 1)
+---@field append? boolean Queue the chord after current sounds instead of playing immediately
+---@field wait? boolean Queue the chord and block until it finishes
+
+---@alias PlayerOne.Mode
+---| "major" | "ionian" | "dorian" | "phrygian" | "lydian" | "mixolydian"
+---| "minor" | "aeolian" | "locrian" | "harmonic_minor" | "pentatonic" | "minor_pentatonic"
+
+---@class PlayerOne.MusicalTypingOptions
+---@field key? string|number Tonic as a note name or MIDI note number (default: "C5")
+---@field mode? PlayerOne.Mode Scale mode (default: "major")
+---@field progression? (integer|string)[] Chord roots as scale degrees or roman numerals (default: { 1, 5, 6, 4 })
+---@field notes_per_chord? integer Keystrokes before moving to the next chord (default: 4)
+
+---@class PlayerOne.ModOptions
+---@field loop? boolean Restart at the song's restart position instead of stopping at the end
+---@field gain? number Gain multiplier, modulated by master_volume (default: 1.0)
+
+---@alias PlayerOne.Step
+---| string # Note name like "C4", MIDI number string, "x" for the instrument pitch or "." for a rest
+---| number # MIDI note number
+---| boolean # true plays the instrument pitch, false is a rest
+
+---@class PlayerOne.Track
+---@field instrument PlayerOne.SoundParams|string Sound template for this track
+---@field pattern string|PlayerOne.Step[] Steps, e.g. "x...x..." or "C4 . E4 G4"
+---@field bpm? number Tempo in beats per minute (default: sequence bpm or 120)
+---@field steps_per_beat? integer Steps per beat (default: sequence value or 4)
+---@field swing? number Off-beat delay, 0.0 straight to 1.0 triplet shuffle (default: 0)
+---@field loops? integer Times to play the pattern, 0 loops until stopped (default: 1)
+---@field gate? number Fraction of a step each note is held (default: 1.0)
+---@field gain? number Track gain multiplier (default: 1.0)
+
+---@class PlayerOne.Sequence
+---@field tracks PlayerOne.Track[] Tracks started together
+---@field bpm? number Default tempo for all tracks
+---@field steps_per_beat? integer Default steps per beat for all tracks
+---@field swing? number Default swing for all tracks
+---@field loops? integer Default loop count for all tracks
+---@field gate? number Default gate for all tracks
 
 ---@alias PlayCallback
 ---| "play" # Play immediately, interrupting current sound
 ---| "append" # Queue sound to play after current sounds
 ---| "play_and_wait" # Play and wait for completion
+---| "play_key" # Play varied by the key just typed, for insert-mode events
 ---| fun(sound: PlayerOne.SoundParams): any # Custom callback function
 
 ---@class PlayerOne.Sound
@@ -57,10 +177,21 @@
 ---@field url string|nil Proxy URL
 ---@field from_env boolean Use system proxy settings
 
+---@class PlayerOne.ValidationIssue
+---@field kind "unknown"|"type"|"clamped"|"invalid" What is wrong with the field
+---@field field string Field name, e.g. "base_frq" or "operators[2].ratio"
+---@field message string Readable description
+---@field suggestion? string Closest known field, for unknown fields
+---@field expected? string Expected type, for type mismatches
+---@field got? string Actual type, for type mismatches
+---@field value? number Value given, for clamped values
+---@field clamped? number Value used instead, for clamped values
+
 ---@class PlayerOne.Config
 ---@field is_enabled boolean Whether the plugin is enabled (default: true)
 ---@field theme string|PlayerOne.Theme Theme name or custom sounds table (default: "chiptune")
 ---@field min_interval number Minimum interval between sounds in seconds (default: 0.05)
 ---@field master_volume? number Master volume for all sounds (0.0-1.0, default: 1.0)
 ---@field binary PlayerOne.BinaryConfig Binary management configuration
+---@field strict boolean Reject sound tables with unknown fields, wrong types or out-of-range values instead of fixing them up (default: false)
 ---@field debug boolean Whether to print the debug message
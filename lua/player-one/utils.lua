@@ -41,14 +41,49 @@ local M = {}
 ---@type number Time of last sound played (in milliseconds)
 local last_play_time = 0
 
+-- Shows every issue when a strict mode error is printed
+local issues_mt = {
+    __tostring = function(issues)
+        local messages = {}
+        for i, issue in ipairs(issues) do
+            messages[i] = issue.message
+        end
+        return table.concat(messages, "; ")
+    end,
+}
+
+---The master volume to apply to a sound, nil when there is none or it is turned off
+---@param opts? PlayerOne.SanitizeOptions Sanitize options
+---@return number|nil master_volume
+local function master_volume(opts)
+    if opts and opts.apply_master_volume == false then
+        return nil
+    end
+    return Config.master_volume
+end
+
+---In strict mode, raise the list of issues with a params table so callers can inspect it
+---@param params PlayerOne.SoundParams Raw parameters to check
+local function check_strict(params)
+    if Config.strict and type(params) == "table" then
+        local issues = Lib.validate(params)
+        if #issues > 0 then
+            error(setmetatable(issues, issues_mt), 0)
+        end
+    end
+end
+
 ---Sanitize and validate sound parameters
 ---@param params PlayerOne.SoundParams|nil Raw parameters to sanitize
+---@param opts? PlayerOne.SanitizeOptions Sanitize options
 ---@return PlayerOne.SoundParams Sanitized parameters
-local function sanitize_params(params)
+local function sanitize_params(params, opts)
     if not params then
         return {}
     end
 
+    check_strict(params)
+
     local valid_keys = {
         "wave_type",
         "base_freq",
@@ -76,7 +111,44 @@ local function sanitize_params(params)
         "sample_rate",
         "sample_size",
         "sound_vol",
+        "glide",
+        "engine",
+        "switch",
+        "stroke",
+        "hold",
+        "algorithm",
+        "operators",
+        "damping",
+        "brightness",
+        "pick_position",
+        "wavetable",
+        "channel",
+        "noise_mode",
+        "noise_period",
+        "level",
+        "envelope",
+        "overtones",
+        "overtone_falloff",
+        "bit_crush",
+        "bit_crush_sweep",
+        "compression",
+    }
+
+    -- Keys that also accept note names like "C#4" or MIDI note numbers like "60"
+    local note_keys = { base_freq = true, freq_limit = true }
+    -- Keys that name an engine or one of its models
+    local string_keys = {
+        engine = true,
+        switch = true,
+        stroke = true,
+        algorithm = true,
+        channel = true,
+        noise_mode = true,
     }
+    -- Keys holding a list of tables with number fields
+    local list_keys = { operators = true }
+    -- Keys holding a list of numbers
+    local number_list_keys = { wavetable = true }
 
     local sanitized = {}
     local temp_params = vim.deepcopy(params) -- Avoid modifying original params table
@@ -94,11 +166,59 @@ local function sanitize_params(params)
     for _, key in ipairs(valid_keys) do
         local value = temp_params[key]
         if value ~= nil then
-            if type(value) ~= "number" then
+            if string_keys[key] then
+                if type(value) ~= "string" then
+                    error("Invalid type for " .. key .. ": expected string, got " .. type(value))
+                end
+                sanitized[key] = value
+            elseif list_keys[key] then
+                if type(value) ~= "table" then
+                    error("Invalid type for " .. key .. ": expected table, got " .. type(value))
+                end
+                for i, item in ipairs(value) do
+                    if type(item) ~= "table" then
+                        error("Invalid type for " .. key .. "[" .. i .. "]: expected table, got " .. type(item))
+                    end
+                    for field, field_value in pairs(item) do
+                        if type(field_value) ~= "number" then
+                            error(
+                                "Invalid type for "
+                                    .. key
+                                    .. "["
+                                    .. i
+                                    .. "]."
+                                    .. tostring(field)
+                                    .. ": expected number, got "
+                                    .. type(field_value)
+                            )
+                        end
+                    end
+                end
+                sanitized[key] = value
+            elseif number_list_keys[key] then
+                if type(value) ~= "table" then
+                    error("Invalid type for " .. key .. ": expected table, got " .. type(value))
+                end
+                for i, item in ipairs(value) do
+                    if type(item) ~= "number" then
+                        error("Invalid type for " .. key .. "[" .. i .. "]: expected number, got " .. type(item))
+                    end
+                end
+                sanitized[key] = value
+            elseif type(value) == "string" and note_keys[key] then
+                -- Note names and MIDI note numbers are resolved by the binary
+                sanitized[key] = value
+            elseif type(value) ~= "number" then
                 error("Invalid type for " .. key .. ": expected number, got " .. type(value))
-            end
-
-            if key == "wave_type" or key == "sample_rate" or key == "sample_size" then
+            elseif
+                key == "wave_type"
+                or key == "sample_rate"
+                or key == "sample_size"
+                or key == "noise_period"
+                or key == "level"
+                or key == "envelope"
+                or key == "overtones"
+            then
                 sanitized[key] = math.floor(value)
             else
                 sanitized[key] = value
@@ -107,9 +227,10 @@ local function sanitize_params(params)
     end
 
     -- Apply master_volume logic
-    if Config.master_volume ~= nil then
+    local master = master_volume(opts)
+    if master ~= nil then
         local base_vol_for_calc = (sanitized.sound_vol == nil) and 1.0 or sanitized.sound_vol
-        local final_vol = base_vol_for_calc * Config.master_volume
+        local final_vol = base_vol_for_calc * master
         sanitized.sound_vol = math.max(0.0, math.min(1.0, final_vol))
     elseif sanitized.sound_vol ~= nil then
         -- Ensure individual sound_vol is clamped even if master_volume is not set
@@ -123,8 +244,9 @@ end
 
 ---Sanitize and validate JSON format sound parameters
 ---@param json_params string JSON string containing sound parameters
+---@param opts? PlayerOne.SanitizeOptions Sanitize options
 ---@return string Sanitized JSON string
-local function sanitize_json_params(json_params)
+local function sanitize_json_params(json_params, opts)
     local ok, params_decoded = pcall(vim.json.decode, json_params)
     if not ok then
         error("Failed to parse sound configuration: " .. params_decoded)
@@ -168,9 +290,10 @@ local function sanitize_json_params(json_params)
     end
 
     -- Apply master_volume logic to params_decoded.sound_vol
-    if Config.master_volume ~= nil then
+    local master = master_volume(opts)
+    if master ~= nil then
         local base_vol_for_calc = (params_decoded.sound_vol == nil) and 1.0 or params_decoded.sound_vol
-        local final_vol = base_vol_for_calc * Config.master_volume
+        local final_vol = base_vol_for_calc * master
         params_decoded.sound_vol = math.max(0.0, math.min(1.0, final_vol))
     elseif params_decoded.sound_vol ~= nil then
         -- Ensure individual sound_vol is clamped even if master_volume is not set
@@ -182,28 +305,89 @@ local function sanitize_json_params(json_params)
     return vim.json.encode(params_decoded)
 end
 
----Process and validate sound parameters before playing
----@param params PlayerOne.SoundParams|PlayerOne.SoundParams[]|string Sound parameters to process
----@param callback function Function to call with processed parameters
----@return any Result from the callback
-local function process_sound_params(params, callback)
+-- bfxr strings whose unsupported fields have already been reported
+local reported_bfxr = {}
+
+---Sanitize and validate a bfxr parameter string
+---@param bfxr_params string Comma-separated bfxr parameters
+---@param opts? PlayerOne.SanitizeOptions Sanitize options
+---@return string Sanitized bfxr string
+local function sanitize_bfxr_params(bfxr_params, opts)
+    local values = vim.split(vim.trim(bfxr_params), ",", { plain = true })
+    for i, value in ipairs(values) do
+        local number = tonumber(vim.trim(value))
+        if number == nil then
+            error("Invalid bfxr value at position " .. i .. ": " .. value)
+        end
+        values[i] = number
+    end
+
+    if not reported_bfxr[bfxr_params] then
+        reported_bfxr[bfxr_params] = true
+        local unsupported = Lib.bfxr_unsupported(bfxr_params)
+        if #unsupported > 0 then
+            vim.notify(
+                "player-one: bfxr fields not supported: " .. table.concat(unsupported, ", "),
+                vim.log.levels.WARN
+            )
+        end
+    end
+
+    -- bfxr squares its master volume, so scale it by the square root of ours
+    local master = master_volume(opts)
+    if master ~= nil and values[2] ~= nil then
+        values[2] = values[2] * math.sqrt(math.max(0.0, master))
+    end
+
+    return table.concat(values, ",")
+end
+
+---Sanitize a sound given as a JSON or bfxr string, or an sfxr.me share link
+---@param params string JSON object, comma-separated bfxr parameters or share link
+---@param opts? PlayerOne.SanitizeOptions Sanitize options
+---@return string Sanitized string
+local function sanitize_string_params(params, opts)
+    if vim.startswith(vim.trim(params), "{") then
+        return sanitize_json_params(params, opts)
+    end
+    if params:find(",", 1, true) then
+        return sanitize_bfxr_params(params, opts)
+    end
+    -- Share links carry no volume, so go through JSON to pick up the master volume
+    return sanitize_json_params(Lib.share_link_to_json(params), opts)
+end
+
+---Check and update the min_interval throttle
+---@return boolean throttled Whether the sound should be skipped
+local function is_throttled()
     local min_interval = Config.min_interval or 0
     local current_time = vim.uv.now()
     local time_diff = (current_time - last_play_time) / 1000 -- Convert to seconds
 
     -- Prevents sounds from playing too frequently
     if time_diff < min_interval then
-        return
+        return true
     end
 
     last_play_time = current_time
+    return false
+end
+
+---Process and validate sound parameters before playing
+---@param params PlayerOne.SoundParams|PlayerOne.SoundParams[]|string Sound parameters to process
+---@param callback function Function to call with processed parameters
+---@return any Result from the callback
+local function process_sound_params(params, callback)
+    if is_throttled() then
+        return
+    end
 
     if type(callback) ~= "function" then
         error("Callback must be a function")
     end
 
     if type(params) == "string" then
-        local sanitized = sanitize_json_params(params)
+        local sanitized = sanitize_string_params(params)
         return callback(sanitized)
     end
 
@@ -230,11 +414,109 @@ local function process_sound_params(params, callback)
     error(string.format("Invalid sound params type: %s", type(params)))
 end
 
+---Sanitize a single sound given as a table, JSON or bfxr string
+---@param sound PlayerOne.SoundParams|string Sound parameters
+---@param opts? PlayerOne.SanitizeOptions Sanitize options
+---@return PlayerOne.SoundParams|string Sanitized parameters
+local function sanitize_sound(sound, opts)
+    if type(sound) == "string" then
+        return sanitize_string_params(sound, opts)
+    end
+    return sanitize_params(sound, opts)
+end
+
+---Sanitize a sound for export, leaving its volume untouched by the master volume
+---@param sound PlayerOne.SoundParams|string Sound parameters
+---@return PlayerOne.SoundParams|string Sanitized parameters
+local function sanitize_export(sound)
+    if type(sound) ~= "table" and type(sound) ~= "string" then
+        error("Invalid sound params type: " .. type(sound))
+    end
+    return sanitize_sound(sound, { apply_master_volume = false })
+end
+
+---Sanitize timeline entries
+---@param entries PlayerOne.TimelineEntry[]|PlayerOne.SoundParams[] Timeline entries or plain sounds
+---@return table[] Sanitized entries
+local function sanitize_timeline(entries)
+    if type(entries) ~= "table" then
+        error("Invalid type for timeline: expected table, got " .. type(entries))
+    end
+
+    local sanitized = {}
+    for i, entry in ipairs(entries) do
+        if type(entry) ~= "table" and type(entry) ~= "string" then
+            error(string.format("Invalid timeline entry at index %d", i))
+        end
+
+        if type(entry) == "table" and entry.sound ~= nil then
+            for _, key in ipairs({ "at_ms", "gain" }) do
+                if entry[key] ~= nil and type(entry[key]) ~= "number" then
+                    error(string.format("Invalid type for %s at index %d: expected number, got %s", key, i, type(entry[key])))
+                end
+            end
+            sanitized[i] = { sound = sanitize_sound(entry.sound), at_ms = entry.at_ms, gain = entry.gain }
+        else
+            sanitized[i] = sanitize_sound(entry)
+        end
+    end
+    return sanitized
+end
+
+-- Per buffer: the character from the last InsertCharPre and the cursor row and line
+-- length after the last insert-mode change
+local typed = {}
+
+---Start a buffer's typed key state from the cursor
+---@param buf integer Buffer handle
+local function reset_typed(buf)
+    typed[buf] = {
+        char = nil,
+        row = vim.api.nvim_win_get_cursor(0)[1],
+        len = #vim.api.nvim_get_current_line(),
+    }
+end
+
+local tracking_keys = false
+
+---Capture typed characters for `typed_key`, once per loaded theme
+local function track_typed_keys()
+    if tracking_keys then
+        return
+    end
+    tracking_keys = true
+
+    vim.api.nvim_create_autocmd("InsertEnter", {
+        group = Config.group,
+        callback = function(args)
+            reset_typed(args.buf)
+        end,
+    })
+    vim.api.nvim_create_autocmd("InsertCharPre", {
+        group = Config.group,
+        callback = function(args)
+            if typed[args.buf] == nil then
+                reset_typed(args.buf)
+            end
+            typed[args.buf].char = vim.v.char
+        end,
+    })
+    vim.api.nvim_create_autocmd("BufWipeout", {
+        group = Config.group,
+        callback = function(args)
+            typed[args.buf] = nil
+        end,
+    })
+end
+
 ---Create autocommands for sound events
 ---@param autocmd string|string[] Neovim autocommand event(s)
 ---@param sound PlayerOne.SoundParams|PlayerOne.SoundParams[] Sound(s) to play
 ---@param callback? PlayCallback How to play the sound
 function M._create_autocmds(autocmd, sound, callback)
+    if callback == "play_key" then
+        track_typed_keys()
+    end
     vim.api.nvim_create_autocmd(autocmd, {
         group = Config.group,
         callback = function()
@@ -249,6 +531,8 @@ function M._create_autocmds(autocmd, sound, callback)
                             M.play(sound)
                         elseif callback == "play_and_wait" then
                             M.play_and_wait(sound)
+                        elseif callback == "play_key" then
+                            M.play_key(M.typed_key(), sound)
                         else
                             error("Invalid callback string: " .. callback)
                         end
@@ -266,6 +550,7 @@ end
 ---Clear all plugin autocommands
 function M.clear_autocmds()
     vim.api.nvim_clear_autocmds({ group = Config.group })
+    tracking_keys = false
 end
 
 ---Load a sound theme
@@ -278,8 +563,6 @@ function M.load_theme(theme)
         end
     end
 
-    M.clear_autocmds()
-
     local themes = Config.themes
     if type(theme) == "string" then
         if not vim.tbl_contains(themes, theme) then
@@ -288,6 +571,21 @@ function M.load_theme(theme)
         theme = require("player-one.themes." .. theme)
     end
 
+    -- Report strict issues once here rather than from every autocmd that plays the sound
+    for _, v in ipairs(theme) do
+        if type(v) == "table" and type(v.sound) == "table" then
+            if type(v.sound[1]) == "table" then
+                for _, sound in ipairs(v.sound) do
+                    check_strict(sound)
+                end
+            else
+                check_strict(v.sound)
+            end
+        end
+    end
+
+    M.clear_autocmds()
+
     for i, v in ipairs(theme) do
         if type(v) ~= "table" then
             error(string.format("Invalid sound configuration at index %d", i))
@@ -313,13 +611,256 @@ function M.play(params)
     return process_sound_params(params, Lib.play)
 end
 
+---Play a keystroke sound varied by the key's character class
+---@param key string Typed character, or "<CR>", "<BS>" or "<Space>"
+---@param params PlayerOne.SoundParams|PlayerOne.SoundParams[]|string Sound parameters
+---@return any Result from sound playback
+function M.play_key(key, params)
+    if type(key) ~= "string" then
+        error("Invalid type for key: expected string, got " .. type(key))
+    end
+    return process_sound_params(params, function(sanitized)
+        return Lib.play_key(key, sanitized)
+    end)
+end
+
+---The key that caused the last insert-mode change in the current buffer
+---@return string key Typed character, "<Space>", "<CR>" or "<BS>"
+function M.typed_key()
+    local buf = vim.api.nvim_get_current_buf()
+    if typed[buf] == nil then
+        reset_typed(buf)
+    end
+    local state = typed[buf]
+    local char, prev_row, prev_len = state.char, state.row, state.len
+    reset_typed(buf)
+
+    -- Printable keys come from InsertCharPre, <CR> and <BS> don't trigger it
+    if char ~= nil and char ~= "" then
+        return char == " " and "<Space>" or char
+    end
+    local row, len = typed[buf].row, typed[buf].len
+    if row > prev_row then
+        return "<CR>"
+    elseif row < prev_row or len < prev_len then
+        return "<BS>"
+    end
+
+    -- Neither, e.g. a completion: use the character before the cursor
+    local col = vim.api.nvim_win_get_cursor(0)[2]
+    local before = vim.fn.matchstr(vim.api.nvim_get_current_line():sub(1, col), ".$")
+    return before == " " and "<Space>" or before
+end
+
 ---Queue a sound to play after current sounds
 ---@param params PlayerOne.SoundParams|PlayerOne.SoundParams[]|string Sound parameters
 ---@return any Result from sound queueing
 function M.append(params)
+    -- Sequences are rendered in one call instead of one FFI call per note
+    if type(params) == "table" and type(params[1]) == "table" and #params > 1 then
+        return M.append_timeline(params)
+    end
     return process_sound_params(params, Lib.append)
 end
 
+---Mix timeline entries into a single sound and play it immediately
+---@param entries PlayerOne.TimelineEntry[]|PlayerOne.SoundParams[] Entries; plain sounds start when the previous one ends
+---@return any Result from playback
+function M.play_timeline(entries)
+    if is_throttled() then
+        return
+    end
+    return Lib.play_timeline(sanitize_timeline(entries))
+end
+
+---Mix timeline entries into a single sound and queue it after current sounds
+---@param entries PlayerOne.TimelineEntry[]|PlayerOne.SoundParams[] Entries; plain sounds start when the previous one ends
+---@return any Result from sound queueing
+function M.append_timeline(entries)
+    if is_throttled() then
+        return
+    end
+    return Lib.append_timeline(sanitize_timeline(entries))
+end
+
+---Play a melody written in MML (Music Macro Language)
+---@param mml string MML source, e.g. "t120 o4 l8 cdefgab>c"; voices are separated by ","
+---@param opts? PlayerOne.MmlOptions Instruments and playback mode
+---@return any Result from playback
+function M.play_mml(mml, opts)
+    if type(mml) ~= "string" then
+        error("Invalid type for mml: expected string, got " .. type(mml))
+    end
+    opts = opts or {}
+    if type(opts) ~= "table" then
+        error("Invalid type for opts: expected table, got " .. type(opts))
+    end
+    if is_throttled() then
+        return
+    end
+
+    local sanitized = { append = opts.append, wait = opts.wait }
+    if opts.instrument ~= nil then
+        sanitized.instrument = sanitize_sound(opts.instrument)
+    end
+    if opts.instruments ~= nil then
+        sanitized.instruments = {}
+        for i, instrument in ipairs(opts.instruments) do
+            sanitized.instruments[i] = sanitize_sound(instrument)
+        end
+    end
+    return Lib.play_mml(mml, sanitized)
+end
+
+---Play a chord, or arpeggiate it when `opts.arp` is set
+---@param root string|number Root note name like "C4" or MIDI note number
+---@param quality? string Chord quality, e.g. "major", "m7", "sus4" (default: "major")
+---@param params PlayerOne.SoundParams|string Sound template for every note
+---@param opts? PlayerOne.ChordOptions Arpeggio and playback mode
+---@return any Result from playback
+function M.play_chord(root, quality, params, opts)
+    if type(root) ~= "string" and type(root) ~= "number" then
+        error("Invalid type for root: expected string or number, got " .. type(root))
+    end
+    if quality ~= nil and type(quality) ~= "string" then
+        error("Invalid type for quality: expected string, got " .. type(quality))
+    end
+    opts = opts or {}
+    if type(opts) ~= "table" then
+        error("Invalid type for opts: expected table, got " .. type(opts))
+    end
+    for _, key in ipairs({ "rate", "octaves" }) do
+        if opts[key] ~= nil and type(opts[key]) ~= "number" then
+            error(string.format("Invalid type for opts.%s: expected number, got %s", key, type(opts[key])))
+        end
+    end
+    if is_throttled() then
+        return
+    end
+
+    local sanitized = {
+        arp = opts.arp,
+        rate = opts.rate,
+        octaves = opts.octaves,
+        append = opts.append,
+        wait = opts.wait,
+    }
+    return Lib.play_chord(root, quality, sanitize_sound(params), sanitized)
+end
+
+---Play a Standard MIDI File (format 0 or 1) through the sound engine
+---@param path string Path to a .mid file
+---@param opts? PlayerOne.MidiOptions Channel instruments and playback mode
+---@return any Result from playback
+function M.play_midi(path, opts)
+    if type(path) ~= "string" then
+        error("Invalid type for path: expected string, got " .. type(path))
+    end
+    opts = opts or {}
+    if type(opts) ~= "table" then
+        error("Invalid type for opts: expected table, got " .. type(opts))
+    end
+    if is_throttled() then
+        return
+    end
+
+    local sanitized = { append = opts.append, wait = opts.wait }
+    if opts.instrument ~= nil then
+        sanitized.instrument = sanitize_sound(opts.instrument)
+    end
+    if opts.channels ~= nil then
+        sanitized.channels = {}
+        for channel, instrument in pairs(opts.channels) do
+            sanitized.channels[channel] = sanitize_sound(instrument)
+        end
+    end
+    return Lib.play_midi(vim.fn.expand(path), sanitized)
+end
+
+---Start a multi-track step sequence
+---@param sequence PlayerOne.Sequence Sequence with tracks and default timing
+---@return integer|nil id Sequence id for `stop_sequence`, or nil when throttled
+function M.play_sequence(sequence)
+    if type(sequence) ~= "table" then
+        error("Invalid type for sequence: expected table, got " .. type(sequence))
+    end
+    if type(sequence.tracks) ~= "table" then
+        error("Invalid type for sequence.tracks: expected table, got " .. type(sequence.tracks))
+    end
+    if is_throttled() then
+        return
+    end
+
+    local sanitized = vim.deepcopy(sequence)
+    for i, track in ipairs(sanitized.tracks) do
+        if type(track) ~= "table" then
+            error(string.format("Invalid track at index %d", i))
+        end
+        if track.instrument == nil then
+            error(string.format("Missing 'instrument' in track at index %d", i))
+        end
+        if track.pattern == nil then
+            error(string.format("Missing 'pattern' in track at index %d", i))
+        end
+        track.instrument = sanitize_sound(track.instrument)
+    end
+    return Lib.play_sequence(sanitized)
+end
+
+---Stop a sequence started with `play_sequence`
+---@param id integer Sequence id
+---@return boolean stopped Whether the sequence was still playing
+function M.stop_sequence(id)
+    return Lib.stop_sequence(id)
+end
+
+---Check whether a sequence is still playing
+---@param id integer Sequence id
+---@return boolean playing
+function M.is_sequence_playing(id)
+    return Lib.is_sequence_playing(id)
+end
+
+---Play a 4 channel ProTracker module (.mod) through the mixer
+---@param path string Path to a .mod file
+---@param opts? PlayerOne.ModOptions Looping and gain
+---@return integer|nil id Module id for `stop_mod`, or nil when throttled
+function M.play_mod(path, opts)
+    if type(path) ~= "string" then
+        error("Invalid type for path: expected string, got " .. type(path))
+    end
+    opts = opts or {}
+    if type(opts) ~= "table" then
+        error("Invalid type for opts: expected table, got " .. type(opts))
+    end
+    if opts.gain ~= nil and type(opts.gain) ~= "number" then
+        error("Invalid type for opts.gain: expected number, got " .. type(opts.gain))
+    end
+    if is_throttled() then
+        return
+    end
+
+    local gain = opts.gain or 1.0
+    if Config.master_volume ~= nil then
+        gain = gain * Config.master_volume
+    end
+    return Lib.play_mod(vim.fn.expand(path), { loop = opts.loop == true, gain = gain })
+end
+
+---Stop a module started with `play_mod`
+---@param id integer Module id
+---@return boolean stopped Whether the module was still playing
+function M.stop_mod(id)
+    return Lib.stop_mod(id)
+end
+
+---Check whether a module is still playing
+---@param id integer Module id
+---@return boolean playing
+function M.is_mod_playing(id)
+    return Lib.is_mod_playing(id)
+end
+
 ---Play a sound and wait for completion
 ---@param params PlayerOne.SoundParams|PlayerOne.SoundParams[]|string Sound parameters
 ---@return any Result from play_and_wait playback
@@ -327,6 +868,191 @@ function M.play_and_wait(params)
     return process_sound_params(params, Lib.play_and_wait)
 end
 
+---Play a sound after a delay, timed by the audio mixer instead of the event loop
+---@param params PlayerOne.SoundParams|PlayerOne.SoundParams[]|string Sound parameters
+---@param delay_ms number Delay in milliseconds
+---@return any Result from scheduling
+function M.play_after(params, delay_ms)
+    if type(delay_ms) ~= "number" then
+        error("Invalid type for delay_ms: expected number, got " .. type(delay_ms))
+    end
+    return process_sound_params(params, function(sanitized)
+        return Lib.play_after(sanitized, delay_ms)
+    end)
+end
+
+---Play a sound at an absolute time on the mixer clock
+---@param params PlayerOne.SoundParams|PlayerOne.SoundParams[]|string Sound parameters
+---@param time_ms number Mixer clock time in milliseconds, see `M.clock`
+---@return any Result from scheduling
+function M.play_at_time(params, time_ms)
+    if type(time_ms) ~= "number" then
+        error("Invalid type for time_ms: expected number, got " .. type(time_ms))
+    end
+    return process_sound_params(params, function(sanitized)
+        return Lib.play_at_time(sanitized, time_ms)
+    end)
+end
+
+---Get the current mixer clock
+---@return number time_ms Milliseconds of audio mixed since the player started
+function M.clock()
+    return Lib.clock()
+end
+
+---Convert a note name or MIDI note number to a frequency
+---@param note string|number Note name like "C#4" or "A4+15c", or a MIDI note number
+---@return number freq Frequency in Hz
+function M.note_to_freq(note)
+    return Lib.note_to_freq(note)
+end
+
+---Convert a frequency to the nearest note name
+---@param freq number Frequency in Hz
+---@return string note Nearest note name, e.g. "A4"
+---@return number cents Offset from the nearest note in cents
+function M.freq_to_note(freq)
+    return Lib.freq_to_note(freq)
+end
+
+---Give every sound played with `play` the next note of a melody in a key and chord progression
+---@param opts? PlayerOne.MusicalTypingOptions|false Melody settings, nil or false turns it off
+function M.set_musical_typing(opts)
+    if not opts then
+        return Lib.set_musical_typing(nil)
+    end
+    if type(opts) ~= "table" then
+        error("Invalid type for opts: expected table, got " .. type(opts))
+    end
+    if opts.key ~= nil and type(opts.key) ~= "string" and type(opts.key) ~= "number" then
+        error("Invalid type for opts.key: expected string or number, got " .. type(opts.key))
+    end
+    if opts.mode ~= nil and type(opts.mode) ~= "string" then
+        error("Invalid type for opts.mode: expected string, got " .. type(opts.mode))
+    end
+    if opts.progression ~= nil and type(opts.progression) ~= "table" then
+        error("Invalid type for opts.progression: expected table, got " .. type(opts.progression))
+    end
+    if opts.notes_per_chord ~= nil and type(opts.notes_per_chord) ~= "number" then
+        error("Invalid type for opts.notes_per_chord: expected number, got " .. type(opts.notes_per_chord))
+    end
+    return Lib.set_musical_typing(opts)
+end
+
+---Load a Scala tuning used for note names and sequencer notes
+---@param scl string Path to a .scl scale file
+---@param kbm? string Path to a .kbm keyboard mapping file
+function M.load_tuning(scl, kbm)
+    if type(scl) ~= "string" then
+        error("Invalid type for scl: expected string, got " .. type(scl))
+    end
+    if kbm ~= nil and type(kbm) ~= "string" then
+        error("Invalid type for kbm: expected string, got " .. type(kbm))
+    end
+    return Lib.load_tuning(vim.fn.expand(scl), kbm and vim.fn.expand(kbm))
+end
+
+---Set the reference pitch, A4 unless a keyboard mapping names another key
+---@param freq number Reference frequency in Hz, e.g. 432
+function M.set_a4(freq)
+    if type(freq) ~= "number" then
+        error("Invalid type for freq: expected number, got " .. type(freq))
+    end
+    return Lib.set_a4(freq)
+end
+
+---Return to 12-tone equal temperament with A4 at 440 Hz
+function M.reset_tuning()
+    return Lib.reset_tuning()
+end
+
+---Export a plain sfxr sound as jsfxr JSON, which sfxr.me can open
+---@param params PlayerOne.SoundParams|string Sound parameters
+---@return string json JSON parameters
+function M.to_json(params)
+    return Lib.to_json(sanitize_export(params))
+end
+
+---List the problems strict mode would reject a sound table for
+---@param params PlayerOne.SoundParams Sound parameters
+---@return PlayerOne.ValidationIssue[] issues Empty when the table is valid
+function M.validate(params)
+    if type(params) ~= "table" then
+        error("Invalid type for params: expected table, got " .. type(params))
+    end
+    return Lib.validate(params)
+end
+
+---Convert a sound to a params table in the units `play` accepts: Hz, seconds and percent
+---@param params PlayerOne.SoundParams|string Sound parameters, JSON, bfxr string or share link
+---@return PlayerOne.SoundParams sound Equivalent params table
+function M.describe(params)
+    return Lib.describe(sanitize_export(params))
+end
+
+---Export a randomised built-in sfxr sound as jsfxr JSON
+---@param name string Preset name: "pickup", "laser", "explosion", "powerup", "hit", "jump" or "blip"
+---@param seed? integer Seed for the randomisation, random when nil
+---@return string json JSON parameters
+function M.preset_to_json(name, seed)
+    if type(name) ~= "string" then
+        error("Invalid type for name: expected string, got " .. type(name))
+    end
+    if seed ~= nil and (type(seed) ~= "number" or seed < 0 or seed ~= math.floor(seed)) then
+        error("Invalid seed: expected a non-negative integer")
+    end
+    return Lib.preset_to_json(name, seed)
+end
+
+---Encode a plain sfxr sound as an sfxr.me share link
+---@param params PlayerOne.SoundParams|string Sound parameters
+---@return string link Share link
+function M.to_share_link(params)
+    return Lib.to_share_link(sanitize_export(params))
+end
+
+---Load a sound from one of sfxr's .sfs settings files
+---@param path string Path to the .sfs file
+---@return string sound The sound as jsfxr JSON, ready to play or use in a theme
+function M.load_sfs(path)
+    if type(path) ~= "string" then
+        error("Invalid type for path: expected string, got " .. type(path))
+    end
+    return Lib.sfs_to_json(vim.fn.expand(path))
+end
+
+---Save a plain sfxr sound as an .sfs settings file
+---@param path string Path to write
+---@param params PlayerOne.SoundParams|string Sound parameters
+function M.save_sfs(path, params)
+    if type(path) ~= "string" then
+        error("Invalid type for path: expected string, got " .. type(path))
+    end
+    return Lib.save_sfs(vim.fn.expand(path), sanitize_export(params))
+end
+
+---Shift the pitch of every subsequently rendered sound
+---@param semitones number Transpose amount in semitones
+---@param cents? number Additional fine tuning in cents
+function M.set_transpose(semitones, cents)
+    if type(semitones) ~= "number" then
+        error("Invalid type for semitones: expected number, got " .. type(semitones))
+    end
+    if cents ~= nil and type(cents) ~= "number" then
+        error("Invalid type for cents: expected number, got " .. type(cents))
+    end
+    return Lib.set_transpose(semitones, cents or 0)
+end
+
+---Scale the duration of every subsequently rendered sound
+---@param factor number Duration multiplier, e.g. 0.5 plays twice as fast
+function M.set_time_scale(factor)
+    if type(factor) ~= "number" then
+        error("Invalid type for factor: expected number, got " .. type(factor))
+    end
+    return Lib.set_time_scale(factor)
+end
+
 ---Stop all currently playing sounds
 ---@return any Result from stop operation
 function M.stop()
@@ -0,0 +1,139 @@
+// bfxr's parameter string: its 32 normalised parameters separated by commas, in the
+// order of the editor's sliders. Most map one to one onto sfxr's; bfxr's second pitch
+// jump and its pitch jump repeat have no equivalent and are reported instead.
+
+use crate::sound::SoundParams;
+use crate::synth::{Extended, Waveform};
+use sfxr::Sample;
+
+/// Editor names of the parameters, in string order.
+pub const FIELDS: [&str; 32] = [
+    "Wave Type",
+    "Master Volume",
+    "Attack Time",
+    "Sustain Time",
+    "Punch",
+    "Decay Time",
+    "Compression",
+    "Frequency",
+    "Frequency Cutoff",
+    "Frequency Slide",
+    "Delta Slide",
+    "Vibrato Depth",
+    "Vibrato Speed",
+    "Harmonics",
+    "Harmonics Falloff",
+    "Pitch Jump Repeat Speed",
+    "Pitch Jump Amount 1",
+    "Pitch Jump Onset 1",
+    "Pitch Jump Amount 2",
+    "Pitch Jump Onset 2",
+    "Square Duty",
+    "Duty Sweep",
+    "Repeat Speed",
+    "Flanger Offset",
+    "Flanger Sweep",
+    "Low-pass Filter Cutoff",
+    "Low-pass Filter Cutoff Sweep",
+    "Low-pass Filter Resonance",
+    "High-pass Filter Cutoff",
+    "High-pass Filter Cutoff Sweep",
+    "Bit Crush",
+    "Bit Crush Sweep",
+];
+
+// bfxr's defaults for fields missing from shorter strings written by older versions
+const DEFAULTS: [f64; 32] = [
+    2.0, 0.5, 0.0, 0.3, 0.0, 0.4, 0.3, 0.3, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+    0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+];
+
+/// Whether `text` looks like a bfxr string rather than JSON.
+pub fn is_bfxr(text: &str) -> bool {
+    let text = text.trim();
+    !text.starts_with('{') && text.contains(',')
+}
+
+/// Parse a bfxr string, returning the sound and the names of fields it could not reproduce.
+pub fn parse(text: &str) -> Result<(SoundParams, Vec<String>), String> {
+    let values = text
+        .trim()
+        .split(',')
+        .map(|field| {
+            field
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .ok_or_else(|| format!("Invalid bfxr value '{}'", field.trim()))
+        })
+        .collect::<Result<Vec<f64>, String>>()?;
+
+    let mut unsupported = Vec::new();
+    let mut p = DEFAULTS;
+    for (i, value) in values.iter().enumerate() {
+        match p.get_mut(i) {
+            Some(slot) => *slot = *value,
+            None => unsupported.push(format!("Field {}", i + 1)),
+        }
+    }
+
+    let mut sample = Sample::new();
+    let mut extended = Extended::default();
+
+    match Waveform::from_index(p[0] as i64) {
+        Ok(waveform) => match waveform.sfxr() {
+            Some(wave_type) => sample.wave_type = wave_type,
+            None => extended.waveform = Some(waveform),
+        },
+        Err(_) => unsupported.push(format!("{} {}", FIELDS[0], p[0])),
+    }
+
+    // Sliders run from 0 to 1, or -1 to 1 for sweeps; clamp like `from_table` so a
+    // hand-edited string can't ask for endless envelopes or thousands of harmonics
+    let unit = |i: usize| p[i].clamp(0.0, 1.0);
+    let signed = |i: usize| p[i].clamp(-1.0, 1.0);
+
+    sample.env_attack = unit(2) as f32;
+    sample.env_sustain = unit(3) as f32;
+    sample.env_punch = signed(4) as f32;
+    sample.env_decay = unit(5) as f32;
+    extended.compression = unit(6) as f32;
+    sample.base_freq = unit(7);
+    sample.freq_limit = unit(8);
+    sample.freq_ramp = signed(9);
+    sample.freq_dramp = signed(10);
+    sample.vib_strength = unit(11);
+    sample.vib_speed = unit(12);
+    extended.overtones = (unit(13) * 10.0).round() as u32;
+    extended.overtone_falloff = unit(14) as f32;
+    // The first pitch jump is sfxr's arpeggio
+    sample.arp_mod = signed(16);
+    sample.arp_speed = unit(17) as f32;
+    sample.duty = unit(20) as f32;
+    sample.duty_ramp = signed(21) as f32;
+    sample.repeat_speed = unit(22) as f32;
+    sample.pha_offset = signed(23) as f32;
+    sample.pha_ramp = signed(24) as f32;
+    sample.lpf_freq = unit(25) as f32;
+    sample.lpf_ramp = signed(26) as f32;
+    sample.lpf_resonance = unit(27) as f32;
+    sample.hpf_freq = unit(28) as f32;
+    sample.hpf_ramp = signed(29) as f32;
+    extended.bit_crush = unit(30) as f32;
+    extended.bit_crush_sweep = signed(31) as f32;
+
+    if p[15] != 0.0 {
+        unsupported.push(FIELDS[15].to_string());
+    }
+    if p[18] != 0.0 {
+        unsupported.push(FIELDS[18].to_string());
+    }
+
+    // bfxr squares the master volume
+    let volume = (p[1] * p[1]) as f32;
+    let params = SoundParams::new(sample)
+        .with_volume(volume.clamp(0.0, 1.0))
+        .with_extended(extended);
+    Ok((params, unsupported))
+}
@@ -0,0 +1,196 @@
+// Chords and arpeggios built from a single SoundParams template
+
+use crate::sound::SoundParams;
+use crate::timeline::{PlayMode, Timeline, TimelineEntry};
+use crate::tuning::Tuning;
+use mlua::prelude::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Semitones above the root for a chord quality such as "major", "m7" or "sus4".
+pub fn intervals(quality: &str) -> Result<&'static [i32], String> {
+    Ok(match quality {
+        "" | "major" | "maj" | "M" => &[0, 4, 7],
+        "minor" | "min" | "m" => &[0, 3, 7],
+        "dim" | "diminished" => &[0, 3, 6],
+        "aug" | "augmented" => &[0, 4, 8],
+        "sus2" => &[0, 2, 7],
+        "sus4" | "sus" => &[0, 5, 7],
+        "5" | "power" => &[0, 7, 12],
+        "6" | "maj6" => &[0, 4, 7, 9],
+        "m6" | "min6" => &[0, 3, 7, 9],
+        "7" | "dom7" => &[0, 4, 7, 10],
+        "maj7" | "M7" => &[0, 4, 7, 11],
+        "m7" | "min7" => &[0, 3, 7, 10],
+        "mmaj7" | "mM7" => &[0, 3, 7, 11],
+        "dim7" => &[0, 3, 6, 9],
+        "m7b5" | "half-dim" => &[0, 3, 6, 10],
+        "7sus4" => &[0, 5, 7, 10],
+        "add9" => &[0, 4, 7, 14],
+        "9" => &[0, 4, 7, 10, 14],
+        "maj9" => &[0, 4, 7, 11, 14],
+        "m9" => &[0, 3, 7, 10, 14],
+        _ => return Err(format!("unknown chord quality '{}'", quality)),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArpPattern {
+    Up,
+    Down,
+    UpDown,
+    Random,
+}
+
+impl ArpPattern {
+    pub fn parse(name: &str) -> Result<ArpPattern, String> {
+        match name {
+            "up" => Ok(ArpPattern::Up),
+            "down" => Ok(ArpPattern::Down),
+            "updown" | "up_down" => Ok(ArpPattern::UpDown),
+            "random" => Ok(ArpPattern::Random),
+            _ => Err(format!("unknown arpeggio pattern '{}'", name)),
+        }
+    }
+}
+
+/// Most octaves an arpeggio can span.
+pub const MAX_OCTAVES: u32 = 8;
+
+pub struct Arpeggio {
+    pub pattern: ArpPattern,
+    /// Milliseconds between note starts.
+    pub rate_ms: f64,
+    /// Number of octaves the chord is spread over.
+    pub octaves: u32,
+}
+
+#[derive(Default)]
+pub struct ChordOptions {
+    /// `None` plays every note at once.
+    pub arpeggio: Option<Arpeggio>,
+    pub mode: PlayMode,
+}
+
+// xorshift64, enough to shuffle a handful of notes
+fn shuffle(notes: &mut [f64]) {
+    let mut state = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+        | 1;
+    for i in (1..notes.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        notes.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+}
+
+/// Order the chord notes for an arpeggio, repeated an octave up `octaves - 1` times.
+pub fn arpeggiate(notes: &[f64], pattern: ArpPattern, octaves: u32) -> Vec<f64> {
+    let mut up: Vec<f64> = (0..octaves.clamp(1, MAX_OCTAVES))
+        .flat_map(|octave| notes.iter().map(move |note| note + 12.0 * octave as f64))
+        .collect();
+
+    match pattern {
+        ArpPattern::Up => up,
+        ArpPattern::Down => {
+            up.reverse();
+            up
+        }
+        ArpPattern::UpDown => {
+            let down: Vec<f64> = up.iter().rev().skip(1).copied().collect();
+            up.extend(down);
+            up
+        }
+        ArpPattern::Random => {
+            shuffle(&mut up);
+            up
+        }
+    }
+}
+
+/// Render a chord on `root` (a fractional MIDI note) with `instrument` as the template.
+pub fn timeline(
+    root: f64,
+    quality: &str,
+    instrument: &SoundParams,
+    options: &ChordOptions,
+    tuning: &Tuning,
+) -> Result<Timeline, String> {
+    let notes: Vec<f64> = intervals(quality)?
+        .iter()
+        .map(|interval| root + *interval as f64)
+        .collect();
+
+    let mut timeline = Timeline::new();
+    match &options.arpeggio {
+        None => {
+            // Keep the summed chord at roughly the level of a single note
+            let gain = 1.0 / (notes.len() as f32).sqrt();
+            for note in notes {
+                let sound = instrument.clone().with_freq(tuning.midi_to_freq(note));
+                timeline.push(TimelineEntry::new(sound).at(0.0).with_gain(gain));
+            }
+        }
+        Some(arpeggio) => {
+            let length = (arpeggio.rate_ms / 1000.0) as f32;
+            for (i, note) in arpeggiate(&notes, arpeggio.pattern, arpeggio.octaves)
+                .into_iter()
+                .enumerate()
+            {
+                let sound = instrument
+                    .clone()
+                    .with_freq(tuning.midi_to_freq(note))
+                    .with_note_length(length);
+                timeline.push(TimelineEntry::new(sound).at(i as f64 * arpeggio.rate_ms));
+            }
+        }
+    }
+    Ok(timeline)
+}
+
+impl FromLua for ChordOptions {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        let table = match value {
+            LuaValue::Nil => return Ok(Self::default()),
+            LuaValue::Table(table) => table,
+            _ => {
+                return Err(mlua::Error::RuntimeError(
+                    "Expected table for chord options".into(),
+                ))
+            }
+        };
+
+        let arpeggio = match table.get::<Option<String>>("arp")? {
+            None => None,
+            Some(name) => {
+                let pattern = ArpPattern::parse(&name).map_err(mlua::Error::RuntimeError)?;
+                let rate_ms = table.get::<Option<f64>>("rate")?.unwrap_or(100.0);
+                if !rate_ms.is_finite() || rate_ms <= 0.0 {
+                    return Err(mlua::Error::RuntimeError(format!(
+                        "Invalid arpeggio rate: {}",
+                        rate_ms
+                    )));
+                }
+                let octaves = table.get::<Option<i64>>("octaves")?.unwrap_or(1);
+                if !(1..=MAX_OCTAVES as i64).contains(&octaves) {
+                    return Err(mlua::Error::RuntimeError(format!(
+                        "Invalid arpeggio octaves: {} (expected 1 to {})",
+                        octaves, MAX_OCTAVES
+                    )));
+                }
+                Some(Arpeggio {
+                    pattern,
+                    rate_ms,
+                    octaves: octaves as u32,
+                })
+            }
+        };
+
+        Ok(Self {
+            arpeggio,
+            mode: PlayMode::from_lua(LuaValue::Table(table), lua)?,
+        })
+    }
+}
@@ -0,0 +1,289 @@
+// 2-4 operator FM synthesis. Operator 1 is always a carrier; the algorithm
+// decides which higher operators modulate which lower ones.
+
+use crate::mixer::SAMPLE_RATE;
+use crate::sound::get_freq;
+use crate::tuning::Tuning;
+use crate::voice::{clamp_seconds, Glide, Voice};
+use mlua::prelude::*;
+use std::f64::consts::TAU;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    /// 4 -> 3 -> 2 -> 1
+    Stack,
+    /// 2 -> 1 and 4 -> 3, two carriers
+    Pairs,
+    /// 2, 3 and 4 all modulate 1
+    Branch,
+    /// Every operator is a carrier (additive)
+    Parallel,
+}
+
+impl Algorithm {
+    pub fn parse(name: &str) -> Result<Algorithm, String> {
+        match name {
+            "stack" => Ok(Algorithm::Stack),
+            "pairs" => Ok(Algorithm::Pairs),
+            "branch" => Ok(Algorithm::Branch),
+            "parallel" => Ok(Algorithm::Parallel),
+            _ => Err(format!("unknown FM algorithm '{}'", name)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::Stack => "stack",
+            Algorithm::Pairs => "pairs",
+            Algorithm::Branch => "branch",
+            Algorithm::Parallel => "parallel",
+        }
+    }
+
+    // Index of the operator modulated by operator `op`, `None` for carriers
+    fn target(self, op: usize) -> Option<usize> {
+        match (self, op) {
+            (_, 0) | (Algorithm::Parallel, _) => None,
+            (Algorithm::Stack, op) => Some(op - 1),
+            (Algorithm::Pairs, 1) => Some(0),
+            (Algorithm::Pairs, 3) => Some(2),
+            (Algorithm::Pairs, _) => None,
+            (Algorithm::Branch, _) => Some(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operator {
+    /// Frequency as a multiple of the note frequency.
+    pub ratio: f64,
+    /// Modulation depth in radians when modulating, output level when a carrier.
+    pub index: f64,
+    pub attack: f64,
+    pub decay: f64,
+    /// Level held after the decay, 0.0-1.0.
+    pub sustain: f64,
+    pub release: f64,
+    /// Self modulation depth in radians.
+    pub feedback: f64,
+}
+
+impl Default for Operator {
+    fn default() -> Self {
+        Self {
+            ratio: 1.0,
+            index: 1.0,
+            attack: 0.002,
+            decay: 0.3,
+            sustain: 0.0,
+            release: 0.1,
+            feedback: 0.0,
+        }
+    }
+}
+
+impl Operator {
+    // ADSR level at `t` seconds for a note held `hold` seconds
+    fn envelope(&self, t: f64, hold: f64) -> f64 {
+        let held = |t: f64| {
+            if t < self.attack {
+                t / self.attack
+            } else if t < self.attack + self.decay {
+                1.0 - (1.0 - self.sustain) * (t - self.attack) / self.decay
+            } else {
+                self.sustain
+            }
+        };
+        if t < hold {
+            held(t)
+        } else if self.release > 0.0 {
+            held(hold) * (1.0 - (t - hold) / self.release).max(0.0)
+        } else {
+            0.0
+        }
+    }
+
+    fn from_table(table: &LuaTable) -> LuaResult<Operator> {
+        let mut op = Operator::default();
+        let fields = [
+            ("ratio", &mut op.ratio),
+            ("index", &mut op.index),
+            ("attack", &mut op.attack),
+            ("decay", &mut op.decay),
+            ("sustain", &mut op.sustain),
+            ("release", &mut op.release),
+            ("feedback", &mut op.feedback),
+        ];
+        for (key, field) in fields {
+            if let Some(value) = table.get::<Option<f64>>(key)? {
+                *field = value;
+            }
+        }
+        if op.ratio <= 0.0 {
+            return Err(mlua::Error::RuntimeError(format!(
+                "Invalid FM operator ratio: {}",
+                op.ratio
+            )));
+        }
+        op.attack = clamp_seconds(op.attack);
+        op.decay = clamp_seconds(op.decay);
+        op.release = clamp_seconds(op.release);
+        op.sustain = op.sustain.clamp(0.0, 1.0);
+        Ok(op)
+    }
+
+    fn to_table(&self, lua: &Lua) -> LuaResult<LuaTable> {
+        let table = lua.create_table()?;
+        table.set("ratio", self.ratio)?;
+        table.set("index", self.index)?;
+        table.set("attack", self.attack)?;
+        table.set("decay", self.decay)?;
+        table.set("sustain", self.sustain)?;
+        table.set("release", self.release)?;
+        table.set("feedback", self.feedback)?;
+        Ok(table)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FmVoice {
+    pub freq: f64,
+    pub operators: Vec<Operator>,
+    pub algorithm: Algorithm,
+    /// Seconds before every operator is released.
+    pub hold: f64,
+}
+
+impl FmVoice {
+    /// A two operator electric piano: a carrier and a decaying modulator an octave up.
+    pub fn new(freq: f64) -> Self {
+        Self {
+            freq,
+            operators: vec![
+                Operator {
+                    decay: 0.8,
+                    ..Default::default()
+                },
+                Operator {
+                    ratio: 2.0,
+                    index: 2.0,
+                    decay: 0.25,
+                    ..Default::default()
+                },
+            ],
+            algorithm: Algorithm::Stack,
+            hold: 0.2,
+        }
+    }
+
+    pub fn from_table(table: &LuaTable, tuning: &Tuning) -> LuaResult<FmVoice> {
+        let mut voice = FmVoice::new(get_freq(table, "base_freq", tuning)?.unwrap_or(440.0));
+
+        if let Some(list) = table.get::<Option<Vec<LuaTable>>>("operators")? {
+            if !(2..=4).contains(&list.len()) {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "FM voices need 2 to 4 operators, got {}",
+                    list.len()
+                )));
+            }
+            voice.operators = list
+                .iter()
+                .map(Operator::from_table)
+                .collect::<LuaResult<_>>()?;
+        }
+        if let Some(name) = table.get::<Option<String>>("algorithm")? {
+            voice.algorithm = Algorithm::parse(&name).map_err(mlua::Error::RuntimeError)?;
+        }
+        if let Some(hold) = table.get::<Option<f64>>("hold")? {
+            voice.hold = clamp_seconds(hold);
+        }
+        Ok(voice)
+    }
+
+    /// Write the keys `from_table` reads into `table`.
+    pub fn to_table(&self, lua: &Lua, table: &LuaTable) -> LuaResult<()> {
+        table.set("base_freq", self.freq)?;
+        let operators = self
+            .operators
+            .iter()
+            .map(|op| op.to_table(lua))
+            .collect::<LuaResult<Vec<_>>>()?;
+        table.set("operators", operators)?;
+        table.set("algorithm", self.algorithm.name())?;
+        table.set("hold", self.hold)
+    }
+}
+
+impl Voice for FmVoice {
+    fn freq(&self) -> f64 {
+        self.freq
+    }
+
+    fn set_freq(&mut self, hz: f64) {
+        self.freq = hz;
+    }
+
+    fn set_note_length(&mut self, seconds: f32) {
+        self.hold = clamp_seconds(seconds as f64);
+    }
+
+    fn scale_time(&mut self, factor: f64) {
+        self.hold = clamp_seconds(self.hold * factor);
+        for op in self.operators.iter_mut() {
+            op.attack = clamp_seconds(op.attack * factor);
+            op.decay = clamp_seconds(op.decay * factor);
+            op.release = clamp_seconds(op.release * factor);
+        }
+    }
+
+    fn duration_samples(&self) -> usize {
+        let release = self
+            .operators
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.algorithm.target(*i).is_none())
+            .fold(0.0f64, |max, (_, op)| max.max(op.release));
+        ((self.hold + release) * SAMPLE_RATE as f64).round() as usize
+    }
+
+    fn render(&self) -> Vec<f32> {
+        self.render_glide(&Glide::NONE)
+    }
+
+    fn render_glide(&self, glide: &Glide) -> Vec<f32> {
+        let rate = SAMPLE_RATE as f64;
+        // Operators past the fourth are ignored, like `from_table` rejects them
+        let count = self.operators.len().min(4);
+        let carriers = (0..count)
+            .filter(|op| self.algorithm.target(*op).is_none())
+            .count()
+            .max(1);
+        let mut phases = vec![0.0f64; count];
+        let mut previous = vec![0.0f64; count];
+        let mut modulation = [0.0f64; 4];
+
+        (0..self.duration_samples())
+            .map(|i| {
+                let t = i as f64 / rate;
+                let freq = self.freq * glide.ratio(i);
+                modulation.fill(0.0);
+                let mut out = 0.0;
+
+                // Modulators have higher indices, so they are evaluated first
+                for op in (0..count).rev() {
+                    let params = &self.operators[op];
+                    let phase = phases[op] + modulation[op] + params.feedback * previous[op];
+                    let value = phase.sin() * params.envelope(t, self.hold);
+                    previous[op] = value;
+                    phases[op] = (phases[op] + TAU * freq * params.ratio / rate) % TAU;
+
+                    match self.algorithm.target(op) {
+                        Some(target) => modulation[target] += value * params.index,
+                        None => out += value * params.index,
+                    }
+                }
+                (out / carriers as f64).clamp(-1.0, 1.0) as f32
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,79 @@
+// Per-key keystroke sounds: each character class has its own pitch, length and
+// level, and every character a fixed detune so a key always sounds the same.
+
+use crate::sound::{SoundParams, Transform};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyClass {
+    Letter,
+    Digit,
+    Punctuation,
+    Space,
+    Enter,
+    Backspace,
+    Other,
+}
+
+impl KeyClass {
+    /// Classify a typed character or a key name like "<CR>", "<BS>" or "space".
+    pub fn of(key: &str) -> KeyClass {
+        match key {
+            " " | "space" | "<Space>" => KeyClass::Space,
+            "\n" | "\r" | "enter" | "<CR>" | "<Enter>" => KeyClass::Enter,
+            "\u{8}" | "\u{7f}" | "backspace" | "<BS>" => KeyClass::Backspace,
+            _ => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c.is_alphabetic() => KeyClass::Letter,
+                    (Some(c), None) if c.is_numeric() => KeyClass::Digit,
+                    (Some(c), None) if !c.is_whitespace() && !c.is_control() => {
+                        KeyClass::Punctuation
+                    }
+                    _ => KeyClass::Other,
+                }
+            }
+        }
+    }
+
+    // (semitones, time scale, gain)
+    fn voicing(self) -> (f64, f64, f32) {
+        match self {
+            KeyClass::Letter | KeyClass::Other => (0.0, 1.0, 1.0),
+            KeyClass::Digit => (2.0, 0.9, 0.95),
+            KeyClass::Punctuation => (5.0, 0.8, 0.9),
+            KeyClass::Space => (-5.0, 1.4, 1.1),
+            KeyClass::Enter => (-12.0, 1.8, 1.2),
+            KeyClass::Backspace => (-3.0, 0.7, 0.85),
+        }
+    }
+}
+
+// FNV-1a, stable across runs unlike the std hasher
+fn hash(key: &str) -> u32 {
+    key.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Transform and gain for a key. Characters within a class are detuned by up to ±40 cents.
+pub fn variation(key: &str) -> (Transform, f32) {
+    let class = KeyClass::of(key);
+    let (semitones, time_scale, gain) = class.voicing();
+    let detune = match class {
+        KeyClass::Letter | KeyClass::Digit | KeyClass::Punctuation => {
+            (hash(key) % 81) as f64 / 100.0 - 0.4
+        }
+        _ => 0.0,
+    };
+    let transform = Transform {
+        transpose: semitones + detune,
+        time_scale,
+    };
+    (transform, gain)
+}
+
+pub fn vary(key: &str, params: &SoundParams) -> SoundParams {
+    let (transform, gain) = variation(key);
+    let volume = (params.volume() * gain).min(1.0);
+    params.transformed(&transform).with_volume(volume)
+}
@@ -1,9 +1,31 @@
+mod bfxr;
+mod chord;
+mod fm;
+mod keys;
 mod lua;
+mod midi;
+mod mixer;
+mod mml;
+mod musical;
+mod nes;
+mod pitch;
 mod player;
+mod pluck;
+mod sequencer;
+mod sfs;
+mod share;
 mod sound;
+mod switch;
+mod synth;
+mod timeline;
+mod tracker;
+mod tuning;
+mod units;
+mod validate;
+mod voice;
 
 pub use player::{PlayError, Player};
-pub use sound::SoundParams;
+pub use sound::{SoundParams, Transform};
 
 #[mlua::lua_module]
 fn libplayerone(lua: &mlua::Lua) -> mlua::Result<mlua::Table> {
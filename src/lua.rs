@@ -1,5 +1,21 @@
+use crate::bfxr;
+use crate::chord::{self, ChordOptions};
+use crate::midi::{self, MidiOptions};
+use crate::mml::{self, MmlOptions};
+use crate::musical::MusicalTyping;
+use crate::pitch;
 use crate::player::Player;
+use crate::pluck::PluckVoice;
+use crate::sequencer::Sequence;
+use crate::sfs;
+use crate::share;
 use crate::sound::SoundParams;
+use crate::switch::{Stroke, SwitchKind, SwitchVoice};
+use crate::timeline::Timeline;
+use crate::tracker::{ModOptions, Module};
+use crate::tuning::Tuning;
+use crate::validate;
+use crate::voice::Engine;
 use mlua::prelude::*;
 use std::sync::Arc;
 
@@ -9,13 +25,44 @@ unsafe impl Sync for Player {}
 pub fn create_lua_module(lua: &Lua) -> LuaResult<LuaTable> {
     let player = Player::new().map_err(|e| mlua::Error::external(e.to_string()))?;
     let player = Arc::new(player);
+    // Lets sound tables resolve note names through the player's tuning
+    lua.set_app_data(player.clone());
     let exports = lua.create_table()?;
 
     register_play(lua, &exports, player.clone())?;
+    register_play_key(lua, &exports, player.clone())?;
     register_append(lua, &exports, player.clone())?;
     register_play_and_wait(lua, &exports, player.clone())?;
     register_play_preset(lua, &exports, player.clone())?;
-    register_stop(lua, &exports, player)?;
+    register_play_after(lua, &exports, player.clone())?;
+    register_play_at_time(lua, &exports, player.clone())?;
+    register_clock(lua, &exports, player.clone())?;
+    register_play_timeline(lua, &exports, player.clone())?;
+    register_append_timeline(lua, &exports, player.clone())?;
+    register_play_mml(lua, &exports, player.clone())?;
+    register_play_midi(lua, &exports, player.clone())?;
+    register_play_chord(lua, &exports, player.clone())?;
+    register_play_sequence(lua, &exports, player.clone())?;
+    register_stop_sequence(lua, &exports, player.clone())?;
+    register_is_sequence_playing(lua, &exports, player.clone())?;
+    register_play_mod(lua, &exports, player.clone())?;
+    register_stop_mod(lua, &exports, player.clone())?;
+    register_is_mod_playing(lua, &exports, player.clone())?;
+    register_set_musical_typing(lua, &exports, player.clone())?;
+    register_set_transpose(lua, &exports, player.clone())?;
+    register_set_time_scale(lua, &exports, player.clone())?;
+    register_stop(lua, &exports, player.clone())?;
+    register_note_to_freq(lua, &exports, player.clone())?;
+    register_freq_to_note(lua, &exports, player.clone())?;
+    register_load_tuning(lua, &exports, player.clone())?;
+    register_set_a4(lua, &exports, player.clone())?;
+    register_reset_tuning(lua, &exports, player)?;
+    register_bfxr_unsupported(lua, &exports)?;
+    register_share_links(lua, &exports)?;
+    register_sfs(lua, &exports)?;
+    register_to_json(lua, &exports)?;
+    register_describe(lua, &exports)?;
+    register_validate(lua, &exports)?;
 
     Ok(exports)
 }
@@ -31,6 +78,17 @@ fn register_play(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResul
     )
 }
 
+fn register_play_key(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "play_key",
+        lua.create_function(move |_, (key, params): (LuaString, SoundParams)| {
+            player
+                .play_key(&key.to_string_lossy(), params)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
 fn register_append(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
     exports.set(
         "append",
@@ -57,24 +115,260 @@ fn register_play_preset(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> L
     exports.set(
         "play_preset",
         lua.create_function(move |_, preset_name: String| {
-            let sample = match preset_name.as_str() {
-                "pickup" => sfxr::Sample::pickup(None),
-                "laser" => sfxr::Sample::laser(None),
-                "explosion" => sfxr::Sample::explosion(None),
-                "powerup" => sfxr::Sample::powerup(None),
-                "hit" => sfxr::Sample::hit(None),
-                "jump" => sfxr::Sample::jump(None),
-                "blip" => sfxr::Sample::blip(None),
-                _ => {
-                    return Err(mlua::Error::external(format!(
-                        "Unknown preset: {}",
-                        preset_name
-                    )))
-                }
-            };
+            player
+                .play(preset(&preset_name, None)?)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
 
+// Built-in sounds; the sfxr ones are randomised around their archetype
+fn preset(name: &str, seed: Option<u64>) -> LuaResult<SoundParams> {
+    let switch =
+        |kind| SoundParams::from_engine(Engine::Switch(SwitchVoice::new(kind, Stroke::Press)));
+    let params = match name {
+        "pickup" => SoundParams::new(sfxr::Sample::pickup(seed)),
+        "laser" => SoundParams::new(sfxr::Sample::laser(seed)),
+        "explosion" => SoundParams::new(sfxr::Sample::explosion(seed)),
+        "powerup" => SoundParams::new(sfxr::Sample::powerup(seed)),
+        "hit" => SoundParams::new(sfxr::Sample::hit(seed)),
+        "jump" => SoundParams::new(sfxr::Sample::jump(seed)),
+        "blip" => SoundParams::new(sfxr::Sample::blip(seed)),
+        "linear" => switch(SwitchKind::Linear),
+        "tactile" => switch(SwitchKind::Tactile),
+        "clicky" => switch(SwitchKind::Clicky),
+        "pluck" => SoundParams::from_engine(Engine::Pluck(PluckVoice::new(220.0))),
+        _ => return Err(mlua::Error::external(format!("Unknown preset: {}", name))),
+    };
+    Ok(params)
+}
+
+fn register_play_after(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "play_after",
+        lua.create_function(move |_, (params, delay_ms): (SoundParams, f64)| {
+            player
+                .play_after(params, delay_ms)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_play_at_time(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "play_at_time",
+        lua.create_function(move |_, (params, time_ms): (SoundParams, f64)| {
             player
-                .play(SoundParams::new(sample))
+                .play_at_time(params, time_ms)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_clock(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "clock",
+        lua.create_function(move |_, ()| {
+            player
+                .clock_ms()
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_play_timeline(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "play_timeline",
+        lua.create_function(move |_, timeline: Timeline| {
+            player
+                .play_timeline(&timeline)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_append_timeline(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "append_timeline",
+        lua.create_function(move |_, timeline: Timeline| {
+            player
+                .append_timeline(&timeline)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_play_mml(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "play_mml",
+        lua.create_function(move |_, (mml, opts): (String, MmlOptions)| {
+            let tuning = player
+                .tuning()
+                .map_err(|e| mlua::Error::external(e.to_string()))?;
+            let timeline = mml::timeline(&mml, &opts.instruments, &tuning)
+                .map_err(|e| mlua::Error::external(e.to_string()))?;
+            player
+                .start_timeline(&timeline, opts.mode)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_play_midi(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "play_midi",
+        lua.create_function(move |_, (path, opts): (String, MidiOptions)| {
+            let notes = midi::load(&path).map_err(|e| mlua::Error::external(e.to_string()))?;
+            let tuning = player
+                .tuning()
+                .map_err(|e| mlua::Error::external(e.to_string()))?;
+            player
+                .start_timeline(&midi::timeline(&notes, &opts, &tuning), opts.mode)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+// A note name string or a (fractional) MIDI note number
+fn midi_note(value: LuaValue) -> LuaResult<f64> {
+    match value {
+        LuaValue::Integer(n) => Ok(n as f64),
+        LuaValue::Number(n) => Ok(n),
+        LuaValue::String(s) => pitch::parse_note(&s.to_str()?).map_err(mlua::Error::external),
+        _ => Err(mlua::Error::external(
+            "Expected note name or MIDI note number",
+        )),
+    }
+}
+
+fn register_play_chord(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "play_chord",
+        lua.create_function(
+            move |_,
+                  (root, quality, params, opts): (
+                LuaValue,
+                Option<String>,
+                SoundParams,
+                ChordOptions,
+            )| {
+                let quality = quality.unwrap_or_default();
+                let tuning = player
+                    .tuning()
+                    .map_err(|e| mlua::Error::external(e.to_string()))?;
+                let timeline = chord::timeline(midi_note(root)?, &quality, &params, &opts, &tuning)
+                    .map_err(mlua::Error::external)?;
+                player
+                    .start_timeline(&timeline, opts.mode)
+                    .map_err(|e| mlua::Error::external(e.to_string()))
+            },
+        )?,
+    )
+}
+
+fn register_play_sequence(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "play_sequence",
+        lua.create_function(move |_, sequence: Sequence| {
+            player
+                .play_sequence(&sequence)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_stop_sequence(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "stop_sequence",
+        lua.create_function(move |_, id: u64| {
+            player
+                .stop_sequence(id)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_is_sequence_playing(
+    lua: &Lua,
+    exports: &LuaTable,
+    player: Arc<Player>,
+) -> LuaResult<()> {
+    exports.set(
+        "is_sequence_playing",
+        lua.create_function(move |_, id: u64| {
+            player
+                .is_sequence_playing(id)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_play_mod(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "play_mod",
+        lua.create_function(move |_, (path, opts): (String, ModOptions)| {
+            let module = Module::load(&path).map_err(|e| mlua::Error::external(e.to_string()))?;
+            player
+                .play_module(Arc::new(module), &opts)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_stop_mod(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "stop_mod",
+        lua.create_function(move |_, id: u64| {
+            player
+                .stop_module(id)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_is_mod_playing(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "is_mod_playing",
+        lua.create_function(move |_, id: u64| {
+            player
+                .is_module_playing(id)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_set_musical_typing(
+    lua: &Lua,
+    exports: &LuaTable,
+    player: Arc<Player>,
+) -> LuaResult<()> {
+    exports.set(
+        "set_musical_typing",
+        lua.create_function(move |_, typing: Option<MusicalTyping>| {
+            player
+                .set_musical_typing(typing)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_set_transpose(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "set_transpose",
+        lua.create_function(move |_, (semitones, cents): (f64, Option<f64>)| {
+            player
+                .set_transpose(semitones, cents.unwrap_or(0.0))
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_set_time_scale(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "set_time_scale",
+        lua.create_function(move |_, factor: f64| {
+            player
+                .set_time_scale(factor)
                 .map_err(|e| mlua::Error::external(e.to_string()))
         })?,
     )
@@ -90,3 +384,137 @@ fn register_stop(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResul
         })?,
     )
 }
+
+fn register_note_to_freq(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "note_to_freq",
+        lua.create_function(move |_, note: LuaValue| {
+            let tuning = player
+                .tuning()
+                .map_err(|e| mlua::Error::external(e.to_string()))?;
+            Ok(tuning.midi_to_freq(midi_note(note)?))
+        })?,
+    )
+}
+
+fn register_freq_to_note(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "freq_to_note",
+        lua.create_function(move |_, freq: f64| {
+            let tuning = player
+                .tuning()
+                .map_err(|e| mlua::Error::external(e.to_string()))?;
+            pitch::freq_to_note(freq, &tuning).map_err(mlua::Error::external)
+        })?,
+    )
+}
+
+fn register_load_tuning(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "load_tuning",
+        lua.create_function(move |_, (scl, kbm): (String, Option<String>)| {
+            let tuning = Tuning::load(&scl, kbm.as_deref())
+                .map_err(|e| mlua::Error::external(e.to_string()))?;
+            player
+                .set_tuning(tuning)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_set_a4(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "set_a4",
+        lua.create_function(move |_, freq: f64| {
+            player
+                .set_reference_freq(freq)
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_reset_tuning(lua: &Lua, exports: &LuaTable, player: Arc<Player>) -> LuaResult<()> {
+    exports.set(
+        "reset_tuning",
+        lua.create_function(move |_, ()| {
+            player
+                .set_tuning(Tuning::default())
+                .map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_bfxr_unsupported(lua: &Lua, exports: &LuaTable) -> LuaResult<()> {
+    exports.set(
+        "bfxr_unsupported",
+        lua.create_function(|_, text: String| {
+            bfxr::parse(&text)
+                .map(|(_, unsupported)| unsupported)
+                .map_err(mlua::Error::external)
+        })?,
+    )
+}
+
+fn register_share_links(lua: &Lua, exports: &LuaTable) -> LuaResult<()> {
+    exports.set(
+        "share_link_to_json",
+        lua.create_function(|_, link: String| {
+            share::decode(&link)
+                .and_then(|params| params.to_json())
+                .map_err(mlua::Error::external)
+        })?,
+    )?;
+    exports.set(
+        "to_share_link",
+        lua.create_function(|_, params: SoundParams| {
+            share::encode(&params).map_err(mlua::Error::external)
+        })?,
+    )
+}
+
+fn register_sfs(lua: &Lua, exports: &LuaTable) -> LuaResult<()> {
+    exports.set(
+        "sfs_to_json",
+        lua.create_function(|_, path: String| {
+            let params = sfs::load(&path).map_err(|e| mlua::Error::external(e.to_string()))?;
+            params.to_json().map_err(mlua::Error::external)
+        })?,
+    )?;
+    exports.set(
+        "save_sfs",
+        lua.create_function(|_, (path, params): (String, SoundParams)| {
+            sfs::save(&path, &params).map_err(|e| mlua::Error::external(e.to_string()))
+        })?,
+    )
+}
+
+fn register_to_json(lua: &Lua, exports: &LuaTable) -> LuaResult<()> {
+    exports.set(
+        "to_json",
+        lua.create_function(|_, params: SoundParams| {
+            params.to_json().map_err(mlua::Error::external)
+        })?,
+    )?;
+    exports.set(
+        "preset_to_json",
+        lua.create_function(|_, (name, seed): (String, Option<u64>)| {
+            preset(&name, seed)?
+                .to_json()
+                .map_err(mlua::Error::external)
+        })?,
+    )
+}
+
+fn register_describe(lua: &Lua, exports: &LuaTable) -> LuaResult<()> {
+    exports.set(
+        "describe",
+        lua.create_function(|_, params: SoundParams| Ok(params))?,
+    )
+}
+
+fn register_validate(lua: &Lua, exports: &LuaTable) -> LuaResult<()> {
+    exports.set(
+        "validate",
+        lua.create_function(|_, table: LuaTable| Ok(validate::check(&validate::fields(&table)?)))?,
+    )
+}
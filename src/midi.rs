@@ -0,0 +1,378 @@
+// Standard MIDI File (format 0 and 1) reader, reduced to timed notes
+
+use crate::sound::SoundParams;
+use crate::timeline::{PlayMode, Timeline, TimelineEntry};
+use crate::tuning::Tuning;
+use mlua::prelude::*;
+use sfxr::Sample;
+use std::collections::{HashMap, VecDeque};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum MidiError {
+    #[error("Failed to read MIDI file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid MIDI file: {0}")]
+    Invalid(String),
+    #[error("Unsupported MIDI file: {0}")]
+    Unsupported(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MidiNote {
+    /// Zero based channel, 9 is General MIDI percussion.
+    pub channel: u8,
+    pub key: u8,
+    pub velocity: u8,
+    pub start_ms: f64,
+    pub length_ms: f64,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], MidiError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| MidiError::Invalid("unexpected end of data".into()))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, MidiError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, MidiError> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, MidiError> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    // Variable length quantity, at most four bytes
+    fn vlq(&mut self) -> Result<u32, MidiError> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.u8()?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(MidiError::Invalid("variable length value too long".into()))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+}
+
+struct TickNote {
+    channel: u8,
+    key: u8,
+    velocity: u8,
+    start: u64,
+    end: u64,
+}
+
+#[derive(Default)]
+struct TrackEvents {
+    notes: Vec<TickNote>,
+    // (tick, microseconds per quarter note)
+    tempos: Vec<(u64, u32)>,
+}
+
+fn parse_track(data: &[u8]) -> Result<TrackEvents, MidiError> {
+    let mut reader = Reader { data, pos: 0 };
+    let mut events = TrackEvents::default();
+    let mut pending: HashMap<(u8, u8), VecDeque<(u64, u8)>> = HashMap::new();
+    let mut tick = 0u64;
+    let mut running_status = None;
+
+    while !reader.is_empty() {
+        tick += reader.vlq()? as u64;
+
+        let mut status = reader.u8()?;
+        let first_data = if status < 0x80 {
+            // Running status reuses the previous channel status byte
+            let data = status;
+            status = running_status
+                .ok_or_else(|| MidiError::Invalid("data byte without status".into()))?;
+            Some(data)
+        } else {
+            None
+        };
+
+        match status {
+            // Meta and sysex events cancel running status
+            0xFF => {
+                running_status = None;
+                let kind = reader.u8()?;
+                let len = reader.vlq()? as usize;
+                let payload = reader.bytes(len)?;
+                match kind {
+                    0x51 if len == 3 => {
+                        let tempo = u32::from_be_bytes([0, payload[0], payload[1], payload[2]]);
+                        events.tempos.push((tick, tempo));
+                    }
+                    0x2F => break,
+                    _ => {}
+                }
+            }
+            0xF0 | 0xF7 => {
+                running_status = None;
+                let len = reader.vlq()? as usize;
+                reader.bytes(len)?;
+            }
+            0x80..=0xEF => {
+                running_status = Some(status);
+                let channel = status & 0x0F;
+                let data1 = match first_data {
+                    Some(data) => data,
+                    None => reader.u8()?,
+                };
+
+                match status & 0xF0 {
+                    0xC0 | 0xD0 => {}
+                    kind => {
+                        let data2 = reader.u8()?;
+                        let note_on = kind == 0x90 && data2 > 0;
+                        let note_off = kind == 0x80 || (kind == 0x90 && data2 == 0);
+                        if note_on {
+                            pending
+                                .entry((channel, data1))
+                                .or_default()
+                                .push_back((tick, data2));
+                        } else if note_off {
+                            if let Some((start, velocity)) = pending
+                                .get_mut(&(channel, data1))
+                                .and_then(|starts| starts.pop_front())
+                            {
+                                events.notes.push(TickNote {
+                                    channel,
+                                    key: data1,
+                                    velocity,
+                                    start,
+                                    end: tick,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {
+                return Err(MidiError::Invalid(format!(
+                    "unknown status byte 0x{:02X}",
+                    status
+                )))
+            }
+        }
+    }
+
+    // Notes still held at the end of the track stop there
+    for ((channel, key), starts) in pending {
+        for (start, velocity) in starts {
+            events.notes.push(TickNote {
+                channel,
+                key,
+                velocity,
+                start,
+                end: tick,
+            });
+        }
+    }
+
+    Ok(events)
+}
+
+enum Timing {
+    TicksPerQuarter(u16),
+    MsPerTick(f64),
+}
+
+// Converts ticks to milliseconds through the merged tempo map
+struct TempoMap {
+    timing: Timing,
+    // (tick, ms at tick, microseconds per quarter note)
+    segments: Vec<(u64, f64, u32)>,
+}
+
+impl TempoMap {
+    fn new(timing: Timing, mut tempos: Vec<(u64, u32)>) -> Self {
+        tempos.sort_by_key(|(tick, _)| *tick);
+        let mut map = Self {
+            timing,
+            segments: vec![(0, 0.0, 500_000)],
+        };
+        for (tick, tempo) in tempos {
+            let ms = map.ticks_to_ms(tick);
+            map.segments.push((tick, ms, tempo));
+        }
+        map
+    }
+
+    fn ticks_to_ms(&self, tick: u64) -> f64 {
+        match self.timing {
+            Timing::MsPerTick(ms) => tick as f64 * ms,
+            Timing::TicksPerQuarter(division) => {
+                let (start, ms, tempo) = self
+                    .segments
+                    .iter()
+                    .rev()
+                    .find(|(start, _, _)| *start <= tick)
+                    .copied()
+                    .unwrap_or((0, 0.0, 500_000));
+                ms + (tick - start) as f64 * tempo as f64 / 1000.0 / division as f64
+            }
+        }
+    }
+}
+
+/// Parse a format 0 or 1 Standard MIDI File into notes sorted by start time.
+pub fn parse(data: &[u8]) -> Result<Vec<MidiNote>, MidiError> {
+    let mut reader = Reader { data, pos: 0 };
+    if reader.bytes(4).ok() != Some(b"MThd".as_slice()) {
+        return Err(MidiError::Invalid("missing MThd header".into()));
+    }
+    let header_len = reader.u32()? as usize;
+    if header_len < 6 {
+        return Err(MidiError::Invalid("header too short".into()));
+    }
+    let format = reader.u16()?;
+    let _track_count = reader.u16()?;
+    let division = reader.u16()?;
+    reader.bytes(header_len - 6)?;
+
+    if format > 1 {
+        return Err(MidiError::Unsupported(format!("format {}", format)));
+    }
+
+    let timing = if division & 0x8000 != 0 {
+        // SMPTE: negative frames per second and ticks per frame
+        let fps = match -((division >> 8) as u8 as i8 as i16) {
+            29 => 29.97,
+            fps => fps as f64,
+        };
+        let ticks_per_frame = (division & 0xFF) as f64;
+        if fps <= 0.0 || ticks_per_frame == 0.0 {
+            return Err(MidiError::Invalid("invalid SMPTE division".into()));
+        }
+        Timing::MsPerTick(1000.0 / (fps * ticks_per_frame))
+    } else if division == 0 {
+        return Err(MidiError::Invalid("division is zero".into()));
+    } else {
+        Timing::TicksPerQuarter(division)
+    };
+
+    let mut notes = Vec::new();
+    let mut tempos = Vec::new();
+    while !reader.is_empty() {
+        let id = reader.bytes(4)?;
+        let len = reader.u32()? as usize;
+        let chunk = reader.bytes(len)?;
+        // Unknown chunks must be skipped
+        if id == b"MTrk" {
+            let events = parse_track(chunk)?;
+            notes.extend(events.notes);
+            tempos.extend(events.tempos);
+        }
+    }
+
+    let tempo_map = TempoMap::new(timing, tempos);
+    let mut notes: Vec<MidiNote> = notes
+        .into_iter()
+        .map(|note| {
+            let start_ms = tempo_map.ticks_to_ms(note.start);
+            MidiNote {
+                channel: note.channel,
+                key: note.key,
+                velocity: note.velocity,
+                start_ms,
+                length_ms: tempo_map.ticks_to_ms(note.end) - start_ms,
+            }
+        })
+        .collect();
+    notes.sort_by(|a, b| a.start_ms.total_cmp(&b.start_ms));
+    Ok(notes)
+}
+
+#[derive(Default)]
+pub struct MidiOptions {
+    /// Instruments by zero based channel.
+    pub channels: HashMap<u8, SoundParams>,
+    /// Instrument for channels without their own, `None` uses a plain square wave.
+    pub instrument: Option<SoundParams>,
+    pub mode: PlayMode,
+}
+
+/// Render notes with the instrument mapped to their channel, velocity as gain.
+pub fn timeline(notes: &[MidiNote], options: &MidiOptions, tuning: &Tuning) -> Timeline {
+    let fallback = options
+        .instrument
+        .clone()
+        .unwrap_or_else(|| SoundParams::new(Sample::new()));
+
+    let mut timeline = Timeline::new();
+    for note in notes {
+        let instrument = options.channels.get(&note.channel).unwrap_or(&fallback);
+        let sound = instrument
+            .clone()
+            .with_freq(tuning.midi_to_freq(note.key as f64))
+            .with_note_length((note.length_ms / 1000.0) as f32);
+        timeline.push(
+            TimelineEntry::new(sound)
+                .at(note.start_ms)
+                .with_gain(note.velocity as f32 / 127.0),
+        );
+    }
+    timeline
+}
+
+pub fn load(path: &str) -> Result<Vec<MidiNote>, MidiError> {
+    parse(&std::fs::read(path)?)
+}
+
+impl FromLua for MidiOptions {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        let table = match value {
+            LuaValue::Nil => return Ok(Self::default()),
+            LuaValue::Table(table) => table,
+            _ => {
+                return Err(mlua::Error::RuntimeError(
+                    "Expected table for MIDI options".into(),
+                ))
+            }
+        };
+
+        // Channels are numbered 1-16 from Lua, as in DAWs
+        let mut channels = HashMap::new();
+        if let Some(map) = table.get::<Option<LuaTable>>("channels")? {
+            for pair in map.pairs::<u8, SoundParams>() {
+                let (channel, instrument) = pair?;
+                if !(1..=16).contains(&channel) {
+                    return Err(mlua::Error::RuntimeError(format!(
+                        "Invalid MIDI channel: {}",
+                        channel
+                    )));
+                }
+                channels.insert(channel - 1, instrument);
+            }
+        }
+
+        Ok(Self {
+            channels,
+            instrument: table.get("instrument")?,
+            mode: PlayMode::from_lua(LuaValue::Table(table), lua)?,
+        })
+    }
+}
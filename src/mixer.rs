@@ -0,0 +1,230 @@
+use rodio::Source;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const SAMPLE_RATE: u32 = 44100;
+
+// Voices are mixed in blocks so the shared state is locked once per block
+// instead of once per sample. Start offsets inside a block stay exact.
+const BLOCK_SIZE: usize = 256;
+
+pub type VoiceSource = Box<dyn Iterator<Item = f32> + Send>;
+
+struct Voice {
+    id: u64,
+    start: u64,
+    source: VoiceSource,
+}
+
+#[derive(Default)]
+struct MixerState {
+    clock: u64,
+    next_id: u64,
+    voices: Vec<Voice>,
+}
+
+/// Sample clock and scheduler shared between the `Player` and the output stream.
+#[derive(Clone, Default)]
+pub struct Mixer {
+    state: Arc<Mutex<MixerState>>,
+}
+
+impl Mixer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The rodio source that drives the mixer, to be played exactly once.
+    pub fn source(&self) -> MixerSource {
+        MixerSource {
+            state: self.state.clone(),
+            buffer: vec![0.0; BLOCK_SIZE],
+            pos: BLOCK_SIZE,
+        }
+    }
+
+    /// Current position of the mixer clock in samples.
+    pub fn clock(&self) -> Result<u64, String> {
+        let state = self.state.lock().map_err(|e| e.to_string())?;
+        Ok(state.clock)
+    }
+
+    /// Schedule `source` to start at the absolute sample position `start`.
+    /// Positions in the past start on the next mixed block.
+    pub fn schedule_at(&self, start: u64, source: VoiceSource) -> Result<u64, String> {
+        let mut state = self.state.lock().map_err(|e| e.to_string())?;
+        state.next_id += 1;
+        let id = state.next_id;
+        state.voices.push(Voice { id, start, source });
+        Ok(id)
+    }
+
+    /// Schedule `source` to start `delay` samples after the current clock.
+    pub fn schedule_after(&self, delay: u64, source: VoiceSource) -> Result<u64, String> {
+        self.schedule_group_after(delay, vec![source])
+    }
+
+    /// Schedule several sources to start on the same sample, sharing one id.
+    pub fn schedule_group_after(
+        &self,
+        delay: u64,
+        sources: Vec<VoiceSource>,
+    ) -> Result<u64, String> {
+        let mut state = self.state.lock().map_err(|e| e.to_string())?;
+        state.next_id += 1;
+        let id = state.next_id;
+        let start = state.clock + delay;
+        for source in sources {
+            state.voices.push(Voice { id, start, source });
+        }
+        Ok(id)
+    }
+
+    /// Remove every scheduled or playing voice with `id`. Returns whether any was found.
+    pub fn cancel(&self, id: u64) -> Result<bool, String> {
+        let mut state = self.state.lock().map_err(|e| e.to_string())?;
+        let before = state.voices.len();
+        state.voices.retain(|voice| voice.id != id);
+        Ok(state.voices.len() != before)
+    }
+
+    /// Whether any voice with `id` is still scheduled or playing.
+    pub fn is_active(&self, id: u64) -> Result<bool, String> {
+        let state = self.state.lock().map_err(|e| e.to_string())?;
+        Ok(state.voices.iter().any(|voice| voice.id == id))
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        let mut state = self.state.lock().map_err(|e| e.to_string())?;
+        state.voices.clear();
+        Ok(())
+    }
+}
+
+pub fn ms_to_samples(ms: f64) -> u64 {
+    (ms.max(0.0) * SAMPLE_RATE as f64 / 1000.0).round() as u64
+}
+
+pub fn samples_to_ms(samples: u64) -> f64 {
+    samples as f64 * 1000.0 / SAMPLE_RATE as f64
+}
+
+/// Repeats a rendered buffer every `period` samples, overlapping tails that
+/// ring past the loop point into the next iteration.
+pub struct LoopSource {
+    buffer: Vec<f32>,
+    period: usize,
+    loops: Option<usize>,
+    pos: usize,
+}
+
+impl LoopSource {
+    /// `loops` of `None` repeats forever.
+    pub fn new(buffer: Vec<f32>, period: usize, loops: Option<usize>) -> Self {
+        Self {
+            buffer,
+            period: period.max(1),
+            loops: loops.map(|n| n.max(1)),
+            pos: 0,
+        }
+    }
+}
+
+impl Iterator for LoopSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let mut iteration = self.pos / self.period;
+        if let Some(loops) = self.loops {
+            if self.pos >= (loops - 1) * self.period + self.buffer.len() {
+                return None;
+            }
+            iteration = iteration.min(loops - 1);
+        }
+
+        let mut sample = 0.0;
+        loop {
+            let offset = self.pos - iteration * self.period;
+            if offset >= self.buffer.len() {
+                break;
+            }
+            sample += self.buffer[offset];
+            if iteration == 0 {
+                break;
+            }
+            iteration -= 1;
+        }
+
+        self.pos += 1;
+        Some(sample)
+    }
+}
+
+fn mix_block(state: &mut MixerState, out: &mut [f32]) {
+    out.fill(0.0);
+    let block_start = state.clock;
+    let block_end = block_start + out.len() as u64;
+
+    state.voices.retain_mut(|voice| {
+        if voice.start >= block_end {
+            return true;
+        }
+
+        let offset = voice.start.saturating_sub(block_start) as usize;
+        for slot in &mut out[offset..] {
+            match voice.source.next() {
+                Some(sample) => *slot += sample,
+                None => return false,
+            }
+        }
+        voice.start = block_end;
+        true
+    });
+
+    for slot in out.iter_mut() {
+        *slot = slot.clamp(-1.0, 1.0);
+    }
+    state.clock = block_end;
+}
+
+pub struct MixerSource {
+    state: Arc<Mutex<MixerState>>,
+    buffer: Vec<f32>,
+    pos: usize,
+}
+
+impl Iterator for MixerSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.pos >= self.buffer.len() {
+            match self.state.lock() {
+                Ok(mut state) => mix_block(&mut state, &mut self.buffer),
+                Err(_) => self.buffer.fill(0.0),
+            }
+            self.pos = 0;
+        }
+
+        let sample = self.buffer[self.pos];
+        self.pos += 1;
+        Some(sample)
+    }
+}
+
+impl Source for MixerSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
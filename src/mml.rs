@@ -0,0 +1,311 @@
+// Music Macro Language, e.g. "t120 o4 l8 cdefgab>c"
+//
+// Supported commands (case-insensitive):
+//   a-g[+#-][len][.]  note with optional accidentals, length and dots
+//   r / p[len][.]     rest
+//   n<midi>           note by MIDI number at the default length
+//   o<n> < >          set octave (0-10), octave down, octave up
+//   l<len>[.]         default length (4 = quarter note)
+//   t<bpm>            tempo in quarter notes per minute
+//   v<0-15>           volume
+//   &                 tie into the next note of the same pitch
+//   ^<len>[.]         extend the previous note or rest
+//   , ;               start the next voice
+// Each voice starts with t120 o4 l4 v15. Whitespace and `|` are ignored.
+
+use crate::sound::SoundParams;
+use crate::timeline::{PlayMode, Timeline, TimelineEntry};
+use crate::tuning::Tuning;
+use mlua::prelude::*;
+use sfxr::Sample;
+use thiserror::Error;
+
+const MAX_OCTAVE: i64 = 10;
+
+#[derive(Error, Debug)]
+#[error("MML error at position {position}: {message}")]
+pub struct MmlError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl MmlError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MmlNote {
+    pub midi: f64,
+    pub start_ms: f64,
+    pub length_ms: f64,
+    pub gain: f32,
+}
+
+struct VoiceState {
+    tempo: f64,
+    octave: i32,
+    // Fraction of a whole note, dots included
+    length: f64,
+    volume: u32,
+    cursor_ms: f64,
+    tie: bool,
+    last_note: Option<usize>,
+}
+
+impl Default for VoiceState {
+    fn default() -> Self {
+        Self {
+            tempo: 120.0,
+            octave: 4,
+            length: 0.25,
+            volume: 15,
+            cursor_ms: 0.0,
+            tie: false,
+            last_note: None,
+        }
+    }
+}
+
+impl VoiceState {
+    fn duration_ms(&self, length: f64) -> f64 {
+        length * 4.0 * 60000.0 / self.tempo
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        digits.parse().ok()
+    }
+
+    fn required_number(&mut self, command: char) -> Result<u32, MmlError> {
+        let position = self.pos;
+        self.number()
+            .ok_or_else(|| MmlError::new(position, format!("expected number after '{}'", command)))
+    }
+
+    fn length(&mut self, default: Option<f64>) -> Result<f64, MmlError> {
+        let position = self.pos;
+        let mut length = match (self.number(), default) {
+            (Some(0), _) => return Err(MmlError::new(position, "length must be at least 1")),
+            (Some(n), _) => 1.0 / n as f64,
+            (None, Some(default)) => default,
+            (None, None) => return Err(MmlError::new(position, "expected length")),
+        };
+
+        let mut dot = length / 2.0;
+        while self.peek() == Some('.') {
+            length += dot;
+            dot /= 2.0;
+            self.pos += 1;
+        }
+        Ok(length)
+    }
+}
+
+fn semitone(letter: char) -> i32 {
+    match letter {
+        'c' => 0,
+        'd' => 2,
+        'e' => 4,
+        'f' => 5,
+        'g' => 7,
+        'a' => 9,
+        _ => 11,
+    }
+}
+
+fn octave(position: usize, octave: i64) -> Result<i32, MmlError> {
+    if !(0..=MAX_OCTAVE).contains(&octave) {
+        return Err(MmlError::new(
+            position,
+            format!(
+                "octave must be between 0 and {}, got {}",
+                MAX_OCTAVE, octave
+            ),
+        ));
+    }
+    Ok(octave as i32)
+}
+
+fn push_note(voice: &mut Vec<MmlNote>, state: &mut VoiceState, midi: f64, length: f64) {
+    let length_ms = state.duration_ms(length);
+    let tied = state.tie && state.last_note.is_some_and(|i| voice[i].midi == midi);
+
+    match state.last_note {
+        Some(i) if tied => voice[i].length_ms += length_ms,
+        _ => {
+            voice.push(MmlNote {
+                midi,
+                start_ms: state.cursor_ms,
+                length_ms,
+                gain: state.volume as f32 / 15.0,
+            });
+            state.last_note = Some(voice.len() - 1);
+        }
+    }
+    state.cursor_ms += length_ms;
+    state.tie = false;
+}
+
+/// Parse MML into one list of notes per voice.
+pub fn parse(src: &str) -> Result<Vec<Vec<MmlNote>>, MmlError> {
+    let mut parser = Parser {
+        chars: src.chars().collect(),
+        pos: 0,
+    };
+    let mut voices = vec![Vec::new()];
+    let mut state = VoiceState::default();
+
+    while let Some(c) = parser.peek() {
+        let position = parser.pos;
+        parser.pos += 1;
+        let voice = voices.last_mut().expect("at least one voice");
+
+        match c.to_ascii_lowercase() {
+            c if c.is_whitespace() || c == '|' => {}
+            ',' | ';' => {
+                voices.push(Vec::new());
+                state = VoiceState::default();
+            }
+            letter @ 'a'..='g' => {
+                let mut note = semitone(letter);
+                while let Some(accidental) = parser.peek() {
+                    note = match accidental {
+                        '+' | '#' => note.checked_add(1),
+                        '-' => note.checked_sub(1),
+                        _ => break,
+                    }
+                    .ok_or_else(|| MmlError::new(parser.pos, "too many accidentals"))?;
+                    parser.pos += 1;
+                }
+                let length = parser.length(Some(state.length))?;
+                let midi = ((state.octave + 1) * 12)
+                    .checked_add(note)
+                    .ok_or_else(|| MmlError::new(position, "note out of range"))?;
+                push_note(voice, &mut state, midi as f64, length);
+            }
+            'n' => {
+                let midi = parser.required_number(c)?;
+                let length = state.length;
+                push_note(voice, &mut state, midi as f64, length);
+            }
+            'r' | 'p' => {
+                let length = parser.length(Some(state.length))?;
+                state.cursor_ms += state.duration_ms(length);
+                state.last_note = None;
+                state.tie = false;
+            }
+            'o' => state.octave = octave(position, parser.required_number(c)? as i64)?,
+            '>' => state.octave = octave(position, state.octave as i64 + 1)?,
+            '<' => state.octave = octave(position, state.octave as i64 - 1)?,
+            'l' => state.length = parser.length(None)?,
+            't' => {
+                let tempo = parser.required_number(c)?;
+                if tempo == 0 {
+                    return Err(MmlError::new(position, "tempo must be at least 1"));
+                }
+                state.tempo = tempo as f64;
+            }
+            'v' => state.volume = parser.required_number(c)?.min(15),
+            '&' => state.tie = true,
+            '^' => {
+                let length_ms = state.duration_ms(parser.length(None)?);
+                if let Some(i) = state.last_note {
+                    voice[i].length_ms += length_ms;
+                }
+                state.cursor_ms += length_ms;
+            }
+            _ => {
+                return Err(MmlError::new(
+                    position,
+                    format!("unexpected character '{}'", c),
+                ))
+            }
+        }
+    }
+
+    Ok(voices)
+}
+
+/// Render MML to a timeline, voice `i` using instrument `i` (cycling when there are fewer).
+pub fn timeline(
+    src: &str,
+    instruments: &[SoundParams],
+    tuning: &Tuning,
+) -> Result<Timeline, MmlError> {
+    let default_instrument = [SoundParams::new(Sample::new())];
+    let instruments = if instruments.is_empty() {
+        &default_instrument[..]
+    } else {
+        instruments
+    };
+
+    let mut timeline = Timeline::new();
+    for (i, voice) in parse(src)?.into_iter().enumerate() {
+        let instrument = &instruments[i % instruments.len()];
+        for note in voice {
+            let sound = instrument
+                .clone()
+                .with_freq(tuning.midi_to_freq(note.midi))
+                .with_note_length((note.length_ms / 1000.0) as f32);
+            timeline.push(
+                TimelineEntry::new(sound)
+                    .at(note.start_ms)
+                    .with_gain(note.gain),
+            );
+        }
+    }
+    Ok(timeline)
+}
+
+#[derive(Default)]
+pub struct MmlOptions {
+    pub instruments: Vec<SoundParams>,
+    pub mode: PlayMode,
+}
+
+impl FromLua for MmlOptions {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        let table = match value {
+            LuaValue::Nil => return Ok(Self::default()),
+            LuaValue::Table(table) => table,
+            _ => {
+                return Err(mlua::Error::RuntimeError(
+                    "Expected table for MML options".into(),
+                ))
+            }
+        };
+
+        let mut instruments = Vec::new();
+        if let Some(instrument) = table.get::<Option<SoundParams>>("instrument")? {
+            instruments.push(instrument);
+        }
+        if let Some(list) = table.get::<Option<Vec<SoundParams>>>("instruments")? {
+            instruments = list;
+        }
+
+        Ok(Self {
+            instruments,
+            mode: PlayMode::from_lua(LuaValue::Table(table), lua)?,
+        })
+    }
+}
@@ -0,0 +1,148 @@
+// Musical typing: every keystroke sound takes the next note of a melody that
+// walks the chord tones of a progression in a fixed key and mode.
+
+use crate::pitch;
+use crate::sound::SoundParams;
+use crate::tuning::Tuning;
+use mlua::prelude::*;
+
+/// Semitones of each scale degree above the tonic.
+pub fn mode_intervals(mode: &str) -> Result<&'static [i32], String> {
+    Ok(match mode {
+        "major" | "ionian" => &[0, 2, 4, 5, 7, 9, 11],
+        "dorian" => &[0, 2, 3, 5, 7, 9, 10],
+        "phrygian" => &[0, 1, 3, 5, 7, 8, 10],
+        "lydian" => &[0, 2, 4, 6, 7, 9, 11],
+        "mixolydian" => &[0, 2, 4, 5, 7, 9, 10],
+        "minor" | "aeolian" => &[0, 2, 3, 5, 7, 8, 10],
+        "locrian" => &[0, 1, 3, 5, 6, 8, 10],
+        "harmonic_minor" => &[0, 2, 3, 5, 7, 8, 11],
+        "pentatonic" | "major_pentatonic" => &[0, 2, 4, 7, 9],
+        "minor_pentatonic" => &[0, 3, 5, 7, 10],
+        _ => return Err(format!("unknown mode '{}'", mode)),
+    })
+}
+
+/// Parse a progression step: a 1-based degree or a roman numeral like "IV" or "vi".
+pub fn parse_degree(step: &str) -> Result<usize, String> {
+    if let Ok(degree) = step.parse::<usize>() {
+        if (1..=7).contains(&degree) {
+            return Ok(degree);
+        }
+    }
+    let degree = match step.to_ascii_uppercase().as_str() {
+        "I" => 1,
+        "II" => 2,
+        "III" => 3,
+        "IV" => 4,
+        "V" => 5,
+        "VI" => 6,
+        "VII" => 7,
+        _ => return Err(format!("invalid progression step '{}'", step)),
+    };
+    Ok(degree)
+}
+
+// Chord tone index for each note within a chord: up the triad to the octave and back
+const CONTOUR: [i64; 6] = [0, 1, 2, 3, 2, 1];
+
+pub struct MusicalTyping {
+    /// MIDI note of the tonic.
+    pub key: f64,
+    pub intervals: &'static [i32],
+    /// 1-based scale degrees the chords are built on.
+    pub progression: Vec<usize>,
+    pub notes_per_chord: usize,
+    position: usize,
+}
+
+impl MusicalTyping {
+    pub fn new(key: f64, intervals: &'static [i32], progression: Vec<usize>) -> Self {
+        Self {
+            key,
+            intervals,
+            progression,
+            notes_per_chord: 4,
+            position: 0,
+        }
+    }
+
+    // MIDI note of a 0-based scale degree, continuing into higher octaves
+    fn degree_note(&self, degree: i64) -> f64 {
+        let len = self.intervals.len() as i64;
+        let octave = degree.div_euclid(len);
+        let interval = self.intervals[degree.rem_euclid(len) as usize];
+        self.key + (octave * 12) as f64 + interval as f64
+    }
+
+    /// MIDI note for the next keystroke.
+    pub fn next_note(&mut self) -> f64 {
+        let per_chord = self.notes_per_chord.max(1);
+        let chord = self.position / per_chord;
+        let root = match self.progression.len() {
+            0 => 0,
+            len => self.progression[chord % len] as i64 - 1,
+        };
+        let tone = CONTOUR[(self.position % per_chord) % CONTOUR.len()];
+        self.position += 1;
+
+        // Chord tones are every other scale degree above the chord root
+        self.degree_note(root + tone * 2)
+    }
+
+    pub fn apply(&mut self, params: SoundParams, tuning: &Tuning) -> SoundParams {
+        let note = self.next_note();
+        params.with_freq(tuning.midi_to_freq(note))
+    }
+}
+
+impl FromLua for MusicalTyping {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let LuaValue::Table(table) = value else {
+            return Err(mlua::Error::RuntimeError(
+                "Expected table for musical typing options".into(),
+            ));
+        };
+
+        let key = match table.get::<LuaValue>("key")? {
+            LuaValue::Nil => 72.0,
+            LuaValue::Integer(n) => n as f64,
+            LuaValue::Number(n) => n,
+            LuaValue::String(s) => pitch::parse_note(&s.to_str()?)
+                .map_err(|e| mlua::Error::RuntimeError(format!("Invalid key: {}", e)))?,
+            other => {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "Invalid key type {}",
+                    other.type_name()
+                )))
+            }
+        };
+
+        let mode = table
+            .get::<Option<String>>("mode")?
+            .unwrap_or_else(|| "major".into());
+        let intervals = mode_intervals(&mode).map_err(mlua::Error::RuntimeError)?;
+
+        let progression = match table.get::<Option<Vec<LuaValue>>>("progression")? {
+            None => vec![1, 5, 6, 4],
+            Some(steps) => {
+                let mut progression = Vec::with_capacity(steps.len());
+                for step in steps {
+                    let degree = match step {
+                        LuaValue::Integer(n) if (1..=7).contains(&n) => Ok(n as usize),
+                        LuaValue::String(s) => parse_degree(&s.to_str()?),
+                        other => Err(format!("invalid progression step {}", other.type_name())),
+                    };
+                    progression.push(degree.map_err(mlua::Error::RuntimeError)?);
+                }
+                progression
+            }
+        };
+
+        let mut typing = Self::new(key, intervals, progression);
+        if let Some(n) = table.get::<Option<usize>>("notes_per_chord")? {
+            typing.notes_per_chord = n.max(1);
+        }
+        Ok(typing)
+    }
+}
@@ -0,0 +1,333 @@
+// NES APU channels: the two pulse channels' hardware duty sequences, the 4-bit
+// stepped triangle and the 15-bit LFSR noise, clocked from the NTSC CPU clock so
+// periods quantise the way they do on the console.
+
+use crate::mixer::SAMPLE_RATE;
+use crate::sound::get_freq;
+use crate::tuning::Tuning;
+use crate::voice::{clamp_seconds, Glide, Voice};
+use mlua::prelude::*;
+
+const CPU_CLOCK: f64 = 1_789_773.0;
+// Envelope and length counters are clocked by the frame counter at 240 Hz
+const QUARTER_FRAME: f64 = 240.0;
+
+const DUTY_SEQUENCES: [[u8; 8]; 4] = [
+    [0, 1, 0, 0, 0, 0, 0, 0],
+    [0, 1, 1, 0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 1, 0, 0, 0],
+    [1, 0, 0, 1, 1, 1, 1, 1],
+];
+const DUTY_PERCENT: [f64; 4] = [12.5, 25.0, 50.0, 75.0];
+
+/// Noise timer periods in CPU cycles.
+pub const NOISE_PERIODS: [u16; 16] = [
+    4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068,
+];
+// Short mode repeats every 93 steps, which sets the pitch of its metallic tone
+const SHORT_NOISE_LENGTH: f64 = 93.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Channel {
+    Pulse,
+    Triangle,
+    Noise,
+}
+
+impl Channel {
+    pub fn parse(name: &str) -> Result<Channel, String> {
+        match name {
+            "pulse" => Ok(Channel::Pulse),
+            "triangle" => Ok(Channel::Triangle),
+            "noise" => Ok(Channel::Noise),
+            _ => Err(format!("unknown NES channel '{}'", name)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Channel::Pulse => "pulse",
+            Channel::Triangle => "triangle",
+            Channel::Noise => "noise",
+        }
+    }
+
+    // CPU cycles per sequencer step for `freq`, quantised to the channel's timer
+    fn step_cycles(self, freq: f64) -> f64 {
+        let freq = freq.max(1.0);
+        match self {
+            // 8 and 32 sequencer steps per period, timer clocked every other CPU cycle
+            Channel::Pulse => {
+                2.0 * ((CPU_CLOCK / (16.0 * freq)).round() - 1.0).clamp(8.0, 2047.0) + 2.0
+            }
+            Channel::Triangle => {
+                ((CPU_CLOCK / (32.0 * freq)).round() - 1.0).clamp(2.0, 2047.0) + 1.0
+            }
+            Channel::Noise => NOISE_PERIODS[noise_index(freq)] as f64,
+        }
+    }
+}
+
+// Closest noise period to `freq`, read as the pitch of the short mode
+fn noise_index(freq: f64) -> usize {
+    let target = CPU_CLOCK / (freq * SHORT_NOISE_LENGTH);
+    (0..NOISE_PERIODS.len())
+        .min_by(|a, b| {
+            let distance = |i: &usize| (NOISE_PERIODS[*i] as f64 / target).ln().abs();
+            distance(a).total_cmp(&distance(b))
+        })
+        .unwrap_or(0)
+}
+
+/// Pitch the noise channel plays at for a period table index.
+pub fn noise_freq(index: usize) -> f64 {
+    CPU_CLOCK / (NOISE_PERIODS[index.min(15)] as f64 * SHORT_NOISE_LENGTH)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NesVoice {
+    pub channel: Channel,
+    pub freq: f64,
+    /// Index into the hardware duty cycles: 12.5%, 25%, 50% and 75%.
+    pub duty: usize,
+    /// 93-step periodic noise instead of the 32767-step sequence.
+    pub short_noise: bool,
+    /// Constant 4-bit volume when there is no envelope.
+    pub level: u8,
+    /// Envelope divider period; the volume falls from 15 every `envelope + 1` quarter frames.
+    pub envelope: Option<u8>,
+    /// Seconds before the length counter silences the channel.
+    pub hold: f64,
+    pub time_scale: f64,
+}
+
+impl NesVoice {
+    pub fn new(channel: Channel, freq: f64) -> Self {
+        Self {
+            channel,
+            freq,
+            duty: 2,
+            short_noise: false,
+            level: 15,
+            envelope: None,
+            hold: 0.2,
+            time_scale: 1.0,
+        }
+    }
+
+    pub fn from_table(table: &LuaTable, tuning: &Tuning) -> LuaResult<NesVoice> {
+        let channel = match table.get::<Option<String>>("channel")? {
+            Some(name) => Channel::parse(&name).map_err(mlua::Error::RuntimeError)?,
+            None => Channel::Pulse,
+        };
+        let mut voice = NesVoice::new(
+            channel,
+            get_freq(table, "base_freq", tuning)?.unwrap_or(440.0),
+        );
+
+        if let Some(percent) = table.get::<Option<f64>>("duty")? {
+            voice.duty = (0..DUTY_PERCENT.len())
+                .min_by(|a, b| {
+                    (DUTY_PERCENT[*a] - percent)
+                        .abs()
+                        .total_cmp(&(DUTY_PERCENT[*b] - percent).abs())
+                })
+                .unwrap_or(2);
+        }
+        if let Some(mode) = table.get::<Option<String>>("noise_mode")? {
+            voice.short_noise = match mode.as_str() {
+                "long" => false,
+                "short" => true,
+                _ => {
+                    return Err(mlua::Error::RuntimeError(format!(
+                        "unknown noise mode '{}'",
+                        mode
+                    )))
+                }
+            };
+        }
+        if let Some(index) = table.get::<Option<usize>>("noise_period")? {
+            voice.freq = noise_freq(index);
+        }
+        if let Some(level) = table.get::<Option<u8>>("level")? {
+            voice.level = level.min(15);
+        }
+        if let Some(period) = table.get::<Option<u8>>("envelope")? {
+            voice.envelope = Some(period.min(15));
+        }
+        if let Some(hold) = table.get::<Option<f64>>("hold")? {
+            voice.hold = clamp_seconds(hold);
+        }
+        Ok(voice)
+    }
+
+    /// Write the keys `from_table` reads into `table`.
+    pub fn to_table(&self, table: &LuaTable) -> LuaResult<()> {
+        table.set("channel", self.channel.name())?;
+        table.set("base_freq", self.freq)?;
+        table.set("duty", DUTY_PERCENT[self.duty])?;
+        table.set(
+            "noise_mode",
+            if self.short_noise { "short" } else { "long" },
+        )?;
+        match self.envelope {
+            Some(period) => table.set("envelope", period)?,
+            None => table.set("level", self.level)?,
+        }
+        table.set("hold", self.hold)
+    }
+
+    // 4-bit volume at `t` seconds
+    fn volume(&self, t: f64) -> u8 {
+        match self.envelope {
+            Some(period) => {
+                let frames = (t * QUARTER_FRAME / self.time_scale) as u64;
+                15u64.saturating_sub(frames / (period as u64 + 1)) as u8
+            }
+            None => self.level,
+        }
+    }
+
+    // Seconds until the envelope reaches 0
+    fn decay_time(&self) -> Option<f64> {
+        if self.channel == Channel::Triangle {
+            return None;
+        }
+        self.envelope
+            .map(|period| 16.0 * (period as f64 + 1.0) / QUARTER_FRAME * self.time_scale)
+    }
+}
+
+// The console's output stage: two high-passes at 90 and 440 Hz and a 14 kHz low-pass
+struct OutputFilter {
+    high_pass: [(f32, f32, f32); 2],
+    low_pass: (f32, f32),
+}
+
+impl OutputFilter {
+    fn new() -> Self {
+        let rate = SAMPLE_RATE as f32;
+        let high = |cutoff: f32| {
+            let rc = 1.0 / (std::f32::consts::TAU * cutoff);
+            (rc / (rc + 1.0 / rate), 0.0, 0.0)
+        };
+        let dt = 1.0 / rate;
+        let rc = 1.0 / (std::f32::consts::TAU * 14000.0);
+        Self {
+            high_pass: [high(90.0), high(440.0)],
+            low_pass: (dt / (rc + dt), 0.0),
+        }
+    }
+
+    fn process(&mut self, mut sample: f32) -> f32 {
+        for (alpha, last_in, last_out) in self.high_pass.iter_mut() {
+            let out = *alpha * (*last_out + sample - *last_in);
+            *last_in = sample;
+            *last_out = out;
+            sample = out;
+        }
+        let (alpha, last) = &mut self.low_pass;
+        *last += *alpha * (sample - *last);
+        *last
+    }
+}
+
+impl Voice for NesVoice {
+    fn freq(&self) -> f64 {
+        self.freq
+    }
+
+    fn set_freq(&mut self, hz: f64) {
+        self.freq = hz;
+    }
+
+    fn set_note_length(&mut self, seconds: f32) {
+        self.hold = clamp_seconds(seconds as f64);
+    }
+
+    fn scale_time(&mut self, factor: f64) {
+        self.hold = clamp_seconds(self.hold * factor);
+        self.time_scale *= factor;
+    }
+
+    fn duration_samples(&self) -> usize {
+        let seconds = match self.decay_time() {
+            Some(decay) => self.hold.min(decay),
+            None => self.hold,
+        };
+        (seconds * SAMPLE_RATE as f64).round() as usize
+    }
+
+    fn render(&self) -> Vec<f32> {
+        self.render_glide(&Glide::NONE)
+    }
+
+    // Glides retune the timer every sample, in the steps its period register allows
+    fn render_glide(&self, glide: &Glide) -> Vec<f32> {
+        let cycles_per_sample = CPU_CLOCK / SAMPLE_RATE as f64;
+        let mut step = 0usize;
+        let mut lfsr: u16 = 1;
+        let mut timer = 0.0;
+        let mut filter = OutputFilter::new();
+
+        // Current DAC input, 0-15, for the sequencer position
+        let output = |step: usize, lfsr: u16, volume: u8| -> u8 {
+            match self.channel {
+                Channel::Pulse => DUTY_SEQUENCES[self.duty][step % 8] * volume,
+                Channel::Triangle => {
+                    let position = (step % 32) as u8;
+                    if position < 16 {
+                        15 - position
+                    } else {
+                        position - 16
+                    }
+                }
+                Channel::Noise => {
+                    if lfsr & 1 == 0 {
+                        volume
+                    } else {
+                        0
+                    }
+                }
+            }
+        };
+        // Non-linear DAC curves, scaled so a full triangle peaks near 1.0
+        let dac = |level: u8| -> f32 {
+            let level = level as f32;
+            let out = match self.channel {
+                Channel::Pulse if level > 0.0 => 95.88 / (8128.0 / level + 100.0),
+                Channel::Triangle if level > 0.0 => 159.79 / (8227.0 / level + 100.0),
+                Channel::Noise if level > 0.0 => 159.79 / (12241.0 / level + 100.0),
+                _ => 0.0,
+            };
+            out * 4.0
+        };
+
+        (0..self.duration_samples())
+            .map(|i| {
+                let volume = self.volume(i as f64 / SAMPLE_RATE as f64);
+                let step_cycles = self.channel.step_cycles(self.freq * glide.ratio(i));
+                timer = timer.min(step_cycles);
+                // Average the DAC over the sample so high pitches don't alias as badly
+                let mut remaining = cycles_per_sample;
+                let mut sum = 0.0;
+                while remaining > 0.0 {
+                    let span = (step_cycles - timer).min(remaining);
+                    sum += dac(output(step, lfsr, volume)) * span as f32;
+                    timer += span;
+                    remaining -= span;
+                    if timer >= step_cycles {
+                        timer -= step_cycles;
+                        step = step.wrapping_add(1);
+                        let tap = if self.short_noise { 6 } else { 1 };
+                        let feedback = (lfsr ^ (lfsr >> tap)) & 1;
+                        lfsr = (lfsr >> 1) | (feedback << 14);
+                    }
+                }
+                filter
+                    .process(sum / cycles_per_sample as f32)
+                    .clamp(-1.0, 1.0)
+            })
+            .collect()
+    }
+}
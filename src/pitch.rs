@@ -0,0 +1,105 @@
+// Musical pitch helpers: note names ("C#4", "Bb3", "A4+15c") and MIDI note numbers
+// converted through a tuning
+
+use crate::tuning::Tuning;
+
+pub const A4_FREQ: f64 = 440.0;
+pub const A4_MIDI: f64 = 69.0;
+
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+fn semitone(letter: char) -> Option<i32> {
+    match letter.to_ascii_uppercase() {
+        'C' => Some(0),
+        'D' => Some(2),
+        'E' => Some(4),
+        'F' => Some(5),
+        'G' => Some(7),
+        'A' => Some(9),
+        'B' => Some(11),
+        _ => None,
+    }
+}
+
+// Parses an optional "+15c" / "-7.5c" cents suffix
+fn parse_cents(rest: &str, input: &str) -> Result<f64, String> {
+    if rest.is_empty() {
+        return Ok(0.0);
+    }
+    let cents = rest
+        .strip_suffix('c')
+        .filter(|c| c.starts_with('+') || c.starts_with('-'))
+        .and_then(|c| c.parse::<f64>().ok())
+        .ok_or_else(|| format!("invalid cents offset in note '{}'", input))?;
+    Ok(cents / 100.0)
+}
+
+/// Parse a note name or MIDI note number string into a fractional MIDI note.
+pub fn parse_note(input: &str) -> Result<f64, String> {
+    let s = input.trim();
+    let mut chars = s.char_indices().peekable();
+
+    let (_, first) = chars.next().ok_or_else(|| "empty note name".to_string())?;
+
+    // MIDI note number, e.g. "60" or "60+10c"
+    if first.is_ascii_digit() {
+        let end = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let note = s[..end]
+            .parse::<f64>()
+            .map_err(|_| format!("invalid MIDI note '{}'", input))?;
+        return Ok(note + parse_cents(&s[end..], input)?);
+    }
+
+    let mut note = semitone(first).ok_or_else(|| format!("invalid note name '{}'", input))? as f64;
+
+    while let Some(&(_, c)) = chars.peek() {
+        match c {
+            '#' => note += 1.0,
+            'b' => note -= 1.0,
+            _ => break,
+        }
+        chars.next();
+    }
+
+    let octave_start = chars.peek().map(|&(i, _)| i).unwrap_or(s.len());
+    let mut octave_end = octave_start;
+    for (i, c) in s[octave_start..].char_indices() {
+        if c.is_ascii_digit() || (i == 0 && c == '-') {
+            octave_end = octave_start + i + 1;
+        } else {
+            break;
+        }
+    }
+    let octave = s[octave_start..octave_end]
+        .parse::<i32>()
+        .map_err(|_| format!("missing octave in note '{}'", input))?;
+
+    note += ((octave + 1) * 12) as f64;
+    Ok(note + parse_cents(&s[octave_end..], input)?)
+}
+
+pub fn note_to_freq(input: &str, tuning: &Tuning) -> Result<f64, String> {
+    parse_note(input).map(|note| tuning.midi_to_freq(note))
+}
+
+/// Nearest note name for `freq` and the remaining offset in cents.
+pub fn freq_to_note(freq: f64, tuning: &Tuning) -> Result<(String, f64), String> {
+    if !freq.is_finite() || freq <= 0.0 {
+        return Err(format!("frequency must be positive, got {}", freq));
+    }
+
+    let midi = tuning.freq_to_midi(freq);
+    let nearest = midi.round();
+    let cents = (midi - nearest) * 100.0;
+    let index = nearest as i64;
+    let name = format!(
+        "{}{}",
+        NOTE_NAMES[index.rem_euclid(12) as usize],
+        index.div_euclid(12) - 1
+    );
+    Ok((name, cents))
+}
@@ -1,4 +1,11 @@
-use crate::sound::SoundParams;
+use crate::keys;
+use crate::mixer::{self, Mixer};
+use crate::musical::MusicalTyping;
+use crate::sequencer::Sequence;
+use crate::sound::{SoundParams, Transform};
+use crate::timeline::{PlayMode, Timeline};
+use crate::tracker::{ModOptions, ModPlayer, Module};
+use crate::tuning::Tuning;
 use rodio::source::Source;
 use rodio::{OutputStream, OutputStreamHandle, Sink};
 use std::sync::{Arc, Mutex};
@@ -10,10 +17,18 @@ pub enum PlayError {
     Device(String),
     #[error("Playback error: {0}")]
     Playback(String),
+    #[error("Invalid parameter: {0}")]
+    InvalidParameter(String),
 }
 
 pub struct Player {
     sink: Arc<Mutex<Sink>>,
+    transform: Mutex<Transform>,
+    tuning: Mutex<Tuning>,
+    musical_typing: Mutex<Option<MusicalTyping>>,
+    // Pitch of the last sound queued with `append`, for the next one to glide from
+    last_appended: Mutex<Option<f64>>,
+    mixer: Mixer,
     _stream: OutputStream,
     _handle: OutputStreamHandle,
 }
@@ -25,25 +40,122 @@ impl Player {
 
         let sink = Sink::try_new(&handle).map_err(|e| PlayError::Device(e.to_string()))?;
 
+        let mixer = Mixer::new();
+        handle
+            .play_raw(mixer.source())
+            .map_err(|e| PlayError::Device(e.to_string()))?;
+
         Ok(Self {
             sink: Arc::new(Mutex::new(sink)),
+            transform: Mutex::new(Transform::default()),
+            tuning: Mutex::new(Tuning::default()),
+            musical_typing: Mutex::new(None),
+            last_appended: Mutex::new(None),
+            mixer,
             _stream: stream,
             _handle: handle,
         })
     }
 
-    pub fn play(&self, params: SoundParams) -> Result<(), PlayError> {
-        let mut generator = params.generator();
+    pub fn transform(&self) -> Result<Transform, PlayError> {
+        let transform = self
+            .transform
+            .lock()
+            .map_err(|e| PlayError::Playback(e.to_string()))?;
+        Ok(*transform)
+    }
+
+    pub fn set_transpose(&self, semitones: f64, cents: f64) -> Result<(), PlayError> {
+        let transpose = semitones + cents / 100.0;
+        if !transpose.is_finite() {
+            return Err(PlayError::InvalidParameter(format!(
+                "transpose must be finite, got {}",
+                transpose
+            )));
+        }
+
+        let mut transform = self
+            .transform
+            .lock()
+            .map_err(|e| PlayError::Playback(e.to_string()))?;
+        transform.transpose = transpose;
+        Ok(())
+    }
+
+    pub fn set_time_scale(&self, factor: f64) -> Result<(), PlayError> {
+        if !factor.is_finite() || factor <= 0.0 {
+            return Err(PlayError::InvalidParameter(format!(
+                "time scale must be a positive number, got {}",
+                factor
+            )));
+        }
+
+        let mut transform = self
+            .transform
+            .lock()
+            .map_err(|e| PlayError::Playback(e.to_string()))?;
+        transform.time_scale = factor;
+        Ok(())
+    }
+
+    /// The tuning note names and MIDI notes are converted through.
+    pub fn tuning(&self) -> Result<Tuning, PlayError> {
+        let tuning = self
+            .tuning
+            .lock()
+            .map_err(|e| PlayError::Playback(e.to_string()))?;
+        Ok(tuning.clone())
+    }
+
+    pub fn set_tuning(&self, tuning: Tuning) -> Result<(), PlayError> {
+        let mut current = self
+            .tuning
+            .lock()
+            .map_err(|e| PlayError::Playback(e.to_string()))?;
+        *current = tuning;
+        Ok(())
+    }
+
+    /// Change the reference pitch of the current tuning, keeping its scale.
+    pub fn set_reference_freq(&self, freq: f64) -> Result<(), PlayError> {
+        let mut tuning = self
+            .tuning
+            .lock()
+            .map_err(|e| PlayError::Playback(e.to_string()))?;
+        tuning
+            .set_reference_freq(freq)
+            .map_err(PlayError::InvalidParameter)
+    }
 
-        let total_duration = (generator.sample.env_attack.powi(2)
-            + generator.sample.env_sustain.powi(2)
-            + generator.sample.env_decay.powi(2))
-            * 100000.0;
-        let buffer_size = total_duration.ceil() as usize;
+    /// Give every sound passed to `play` the next note of a melody, `None` turns it off.
+    pub fn set_musical_typing(&self, typing: Option<MusicalTyping>) -> Result<(), PlayError> {
+        let mut current = self
+            .musical_typing
+            .lock()
+            .map_err(|e| PlayError::Playback(e.to_string()))?;
+        *current = typing;
+        Ok(())
+    }
 
-        let mut buffer = vec![0.0; buffer_size];
-        generator.generate(&mut buffer);
+    fn render(&self, params: &SoundParams) -> Result<Vec<f32>, PlayError> {
+        let transform = self.transform()?;
+        Ok(params.transformed(&transform).render())
+    }
 
+    // Give `params` the next note of the melody when musical typing is on
+    fn apply_musical_typing(&self, params: SoundParams) -> Result<SoundParams, PlayError> {
+        let mut typing = self
+            .musical_typing
+            .lock()
+            .map_err(|e| PlayError::Playback(e.to_string()))?;
+        Ok(match typing.as_mut() {
+            Some(typing) => typing.apply(params, &self.tuning()?),
+            None => params,
+        })
+    }
+
+    fn play_now(&self, params: &SoundParams) -> Result<(), PlayError> {
+        let buffer = self.render(params)?;
         let source = rodio::buffer::SamplesBuffer::new(1, 44100, buffer);
 
         let _ = self._handle.play_raw(source.convert_samples());
@@ -51,18 +163,84 @@ impl Player {
         Ok(())
     }
 
+    pub fn play(&self, params: SoundParams) -> Result<(), PlayError> {
+        let params = self.apply_musical_typing(params)?;
+        self.play_now(&params)
+    }
+
+    /// The sound `play_key` plays: the musical typing note, if on, detuned for `key`.
+    pub fn key_sound(&self, key: &str, params: SoundParams) -> Result<SoundParams, PlayError> {
+        Ok(keys::vary(key, &self.apply_musical_typing(params)?))
+    }
+
+    /// Play a keystroke sound varied by the character class of `key`.
+    pub fn play_key(&self, key: &str, params: SoundParams) -> Result<(), PlayError> {
+        self.play_now(&self.key_sound(key, params)?)
+    }
+
+    /// Queue a sound after current sounds. One with a glide bends from the pitch of the
+    /// sound queued before it, while that is still playing.
     pub fn append(&self, params: SoundParams) -> Result<(), PlayError> {
-        let mut generator = params.generator();
+        let sound = params.transformed(&self.transform()?);
+        let sink = self
+            .sink
+            .lock()
+            .map_err(|e| PlayError::Playback(e.to_string()))?;
+        let mut last_appended = self
+            .last_appended
+            .lock()
+            .map_err(|e| PlayError::Playback(e.to_string()))?;
+
+        let buffer = match *last_appended {
+            Some(from_hz) if sound.glide_ms() > 0.0 && !sink.empty() => sound.render_glide(from_hz),
+            _ => sound.render(),
+        };
+        *last_appended = Some(sound.freq());
 
-        let total_duration = (generator.sample.env_attack.powi(2)
-            + generator.sample.env_sustain.powi(2)
-            + generator.sample.env_decay.powi(2))
-            * 100000.0;
-        let buffer_size = total_duration.ceil() as usize;
+        sink.append(rodio::buffer::SamplesBuffer::new(1, 44100, buffer));
+        Ok(())
+    }
 
-        let mut buffer = vec![0.0; buffer_size];
-        generator.generate(&mut buffer);
+    /// Milliseconds elapsed on the mixer clock, the time base of `play_at_time`.
+    pub fn clock_ms(&self) -> Result<f64, PlayError> {
+        let clock = self.mixer.clock().map_err(PlayError::Playback)?;
+        Ok(mixer::samples_to_ms(clock))
+    }
 
+    /// Start a sound `delay_ms` after the current mixer clock.
+    pub fn play_after(&self, params: SoundParams, delay_ms: f64) -> Result<(), PlayError> {
+        let buffer = self.render(&params)?;
+        self.mixer
+            .schedule_after(mixer::ms_to_samples(delay_ms), Box::new(buffer.into_iter()))
+            .map_err(PlayError::Playback)?;
+        Ok(())
+    }
+
+    /// Start a sound at `time_ms` on the mixer clock. Times in the past play immediately.
+    pub fn play_at_time(&self, params: SoundParams, time_ms: f64) -> Result<(), PlayError> {
+        let buffer = self.render(&params)?;
+        self.mixer
+            .schedule_at(mixer::ms_to_samples(time_ms), Box::new(buffer.into_iter()))
+            .map_err(PlayError::Playback)?;
+        Ok(())
+    }
+
+    /// Mix every timeline entry into one source and start it immediately.
+    pub fn play_timeline(&self, timeline: &Timeline) -> Result<(), PlayError> {
+        let buffer = timeline
+            .render(&self.transform()?)
+            .map_err(PlayError::InvalidParameter)?;
+        self.mixer
+            .schedule_after(0, Box::new(buffer.into_iter()))
+            .map_err(PlayError::Playback)?;
+        Ok(())
+    }
+
+    /// Mix every timeline entry into one source and queue it after current sounds.
+    pub fn append_timeline(&self, timeline: &Timeline) -> Result<(), PlayError> {
+        let buffer = timeline
+            .render(&self.transform()?)
+            .map_err(PlayError::InvalidParameter)?;
         let source = rodio::buffer::SamplesBuffer::new(1, 44100, buffer);
 
         let sink = self
@@ -71,6 +249,69 @@ impl Player {
             .map_err(|e| PlayError::Playback(e.to_string()))?;
 
         sink.append(source);
+        // A timeline has no single pitch for the next sound to glide from
+        *self
+            .last_appended
+            .lock()
+            .map_err(|e| PlayError::Playback(e.to_string()))? = None;
+        Ok(())
+    }
+
+    pub fn start_timeline(&self, timeline: &Timeline, mode: PlayMode) -> Result<(), PlayError> {
+        match mode {
+            PlayMode::Play => self.play_timeline(timeline),
+            PlayMode::Append => self.append_timeline(timeline),
+            PlayMode::Wait => self.play_timeline_and_wait(timeline),
+        }
+    }
+
+    /// Start every track of a sequence on the same sample. Returns an id for `stop_sequence`.
+    pub fn play_sequence(&self, sequence: &Sequence) -> Result<u64, PlayError> {
+        let sources = sequence
+            .sources(&self.transform()?, &self.tuning()?)
+            .map_err(PlayError::InvalidParameter)?;
+        self.mixer
+            .schedule_group_after(0, sources)
+            .map_err(PlayError::Playback)
+    }
+
+    /// Stop a sequence started by `play_sequence`. Returns whether it was still playing.
+    pub fn stop_sequence(&self, id: u64) -> Result<bool, PlayError> {
+        self.mixer.cancel(id).map_err(PlayError::Playback)
+    }
+
+    pub fn is_sequence_playing(&self, id: u64) -> Result<bool, PlayError> {
+        self.mixer.is_active(id).map_err(PlayError::Playback)
+    }
+
+    /// Stream a tracker module through the mixer. Returns an id for `stop_module`.
+    pub fn play_module(&self, module: Arc<Module>, options: &ModOptions) -> Result<u64, PlayError> {
+        let source = ModPlayer::new(module, options.looping, options.gain);
+        self.mixer
+            .schedule_after(0, Box::new(source))
+            .map_err(PlayError::Playback)
+    }
+
+    /// Stop a module started by `play_module`. Returns whether it was still playing.
+    pub fn stop_module(&self, id: u64) -> Result<bool, PlayError> {
+        self.mixer.cancel(id).map_err(PlayError::Playback)
+    }
+
+    pub fn is_module_playing(&self, id: u64) -> Result<bool, PlayError> {
+        self.mixer.is_active(id).map_err(PlayError::Playback)
+    }
+
+    /// Queue a timeline and block until it has finished playing.
+    pub fn play_timeline_and_wait(&self, timeline: &Timeline) -> Result<(), PlayError> {
+        self.append_timeline(timeline)?;
+
+        let sink = self
+            .sink
+            .lock()
+            .map_err(|e| PlayError::Playback(e.to_string()))?;
+
+        sink.sleep_until_end();
+
         Ok(())
     }
 
@@ -93,6 +334,7 @@ impl Player {
             .lock()
             .map_err(|e| PlayError::Playback(e.to_string()))?;
         sink.stop();
+        self.mixer.clear().map_err(PlayError::Playback)?;
         Ok(())
     }
 }
@@ -0,0 +1,151 @@
+// Karplus-Strong plucked string: a burst of shaped noise recirculating through a
+// delay line one period long, losing a little energy and treble on every pass.
+
+use crate::mixer::SAMPLE_RATE;
+use crate::sound::get_freq;
+use crate::tuning::Tuning;
+use crate::voice::{clamp_seconds, normalize, Noise, Voice};
+use mlua::prelude::*;
+
+// Seconds to mute the string once the note is released
+const MUTE: f64 = 0.05;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluckVoice {
+    pub freq: f64,
+    /// Energy lost per pass, 0.0 (rings on) to 1.0 (dead thud).
+    pub damping: f64,
+    /// Treble in the pluck and in the string, 0.0 (soft thumb) to 1.0 (pick).
+    pub brightness: f64,
+    /// Where the string is plucked, as a fraction of its length from the bridge.
+    pub pick_position: f64,
+    /// Seconds the string rings before it is muted.
+    pub hold: f64,
+    pub time_scale: f64,
+}
+
+impl PluckVoice {
+    pub fn new(freq: f64) -> Self {
+        Self {
+            freq,
+            damping: 0.3,
+            brightness: 0.5,
+            pick_position: 0.2,
+            hold: 0.6,
+            time_scale: 1.0,
+        }
+    }
+
+    pub fn from_table(table: &LuaTable, tuning: &Tuning) -> LuaResult<PluckVoice> {
+        let mut voice = PluckVoice::new(get_freq(table, "base_freq", tuning)?.unwrap_or(220.0));
+        if let Some(damping) = table.get::<Option<f64>>("damping")? {
+            voice.damping = damping.clamp(0.0, 1.0);
+        }
+        if let Some(brightness) = table.get::<Option<f64>>("brightness")? {
+            voice.brightness = brightness.clamp(0.0, 1.0);
+        }
+        if let Some(position) = table.get::<Option<f64>>("pick_position")? {
+            voice.pick_position = position.clamp(0.0, 1.0);
+        }
+        if let Some(hold) = table.get::<Option<f64>>("hold")? {
+            voice.hold = clamp_seconds(hold);
+        }
+        Ok(voice)
+    }
+
+    /// Write the keys `from_table` reads into `table`.
+    pub fn to_table(&self, table: &LuaTable) -> LuaResult<()> {
+        table.set("base_freq", self.freq)?;
+        table.set("damping", self.damping)?;
+        table.set("brightness", self.brightness)?;
+        table.set("pick_position", self.pick_position)?;
+        table.set("hold", self.hold)
+    }
+
+    // Noise burst filling the delay line, low-passed by the brightness and
+    // comb-filtered by the pick position
+    fn excitation(&self, length: usize) -> Vec<f32> {
+        let mut noise = Noise::new(self.freq.to_bits() as u32 ^ length as u32);
+        let smoothing = (0.1 + 0.9 * self.brightness) as f32;
+        let mut level = 0.0f32;
+        let burst: Vec<f32> = (0..length)
+            .map(|_| {
+                level += smoothing * (noise.sample() - level);
+                level
+            })
+            .collect();
+
+        let offset = ((self.pick_position * length as f64).round() as usize).clamp(1, length - 1);
+        (0..length)
+            .map(|i| burst[i] - if i >= offset { burst[i - offset] } else { 0.0 })
+            .collect()
+    }
+}
+
+impl Voice for PluckVoice {
+    fn freq(&self) -> f64 {
+        self.freq
+    }
+
+    fn set_freq(&mut self, hz: f64) {
+        self.freq = hz;
+    }
+
+    fn set_note_length(&mut self, seconds: f32) {
+        self.hold = clamp_seconds(seconds as f64);
+    }
+
+    fn scale_time(&mut self, factor: f64) {
+        self.hold = clamp_seconds(self.hold * factor);
+        self.time_scale *= factor;
+    }
+
+    fn duration_samples(&self) -> usize {
+        ((self.hold + MUTE * self.time_scale) * SAMPLE_RATE as f64).round() as usize
+    }
+
+    fn render(&self) -> Vec<f32> {
+        let rate = SAMPLE_RATE as f64;
+        // The averaging filter delays by half a sample; an allpass makes up the
+        // fraction so the string stays in tune at high pitches
+        let period = rate / self.freq.clamp(20.0, rate / 4.0);
+        let length = (period - 0.6).floor() as usize;
+        let fraction = period - 0.5 - length as f64;
+        let allpass = ((1.0 - fraction) / (1.0 + fraction)) as f32;
+
+        // Per-pass loss stretched with the time scale so longer notes ring longer
+        let gain = (1.0 - 0.03 * self.damping).powf(1.0 / self.time_scale) as f32;
+        let stretch = (0.5 - 0.45 * self.brightness) as f32;
+
+        let mut line = self.excitation(length);
+        let mut position = 0;
+        let mut previous = 0.0f32;
+        let (mut allpass_in, mut allpass_out) = (0.0f32, 0.0f32);
+
+        let total = self.duration_samples();
+        let mute_start = (self.hold * rate) as usize;
+        let mute_len = total.saturating_sub(mute_start).max(1) as f32;
+
+        let mut out: Vec<f32> = (0..total)
+            .map(|i| {
+                let sample = line[position];
+                let filtered = gain * ((1.0 - stretch) * sample + stretch * previous);
+                previous = sample;
+                let tuned = allpass * filtered + allpass_in - allpass * allpass_out;
+                allpass_in = filtered;
+                allpass_out = tuned;
+                line[position] = tuned;
+                position = (position + 1) % length;
+
+                if i >= mute_start {
+                    sample * (1.0 - (i - mute_start) as f32 / mute_len)
+                } else {
+                    sample
+                }
+            })
+            .collect();
+
+        normalize(&mut out, 1.0);
+        out
+    }
+}
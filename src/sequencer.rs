@@ -0,0 +1,208 @@
+// Step sequencer: every track loops its own pattern with its own tempo, swing
+// and loop count. Tracks of one sequence start on the same mixer sample.
+
+use crate::mixer::{self, LoopSource, VoiceSource};
+use crate::pitch;
+use crate::sound::{SoundParams, Transform};
+use crate::timeline::{Timeline, TimelineEntry};
+use crate::tuning::Tuning;
+use mlua::prelude::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    Rest,
+    /// Play the instrument at its own pitch.
+    Hit,
+    /// Play the instrument at a fractional MIDI note.
+    Note(f64),
+}
+
+impl Step {
+    pub fn parse(token: &str) -> Result<Step, String> {
+        match token {
+            "." | "-" | "_" => Ok(Step::Rest),
+            "x" | "X" => Ok(Step::Hit),
+            note => pitch::parse_note(note).map(Step::Note),
+        }
+    }
+}
+
+/// Parse a pattern string. Without whitespace every character is a step
+/// (`"x..x"`), otherwise steps are whitespace separated tokens (`"C4 . E4 x"`).
+pub fn parse_pattern(pattern: &str) -> Result<Vec<Step>, String> {
+    if pattern.contains(char::is_whitespace) {
+        pattern.split_whitespace().map(Step::parse).collect()
+    } else {
+        pattern
+            .chars()
+            .map(|c| Step::parse(c.encode_utf8(&mut [0; 4])))
+            .collect()
+    }
+}
+
+pub struct Track {
+    pub instrument: SoundParams,
+    pub steps: Vec<Step>,
+    pub bpm: f64,
+    pub steps_per_beat: u32,
+    /// 0.0 is straight, 1.0 delays every off-beat step to a triplet shuffle.
+    pub swing: f64,
+    /// `None` loops until stopped.
+    pub loops: Option<usize>,
+    /// Fraction of a step each note is held for.
+    pub gate: f32,
+    pub gain: f32,
+}
+
+impl Track {
+    pub fn new(instrument: SoundParams, steps: Vec<Step>) -> Self {
+        Self {
+            instrument,
+            steps,
+            bpm: 120.0,
+            steps_per_beat: 4,
+            swing: 0.0,
+            loops: Some(1),
+            gate: 1.0,
+            gain: 1.0,
+        }
+    }
+
+    pub fn step_ms(&self) -> f64 {
+        60000.0 / (self.bpm * self.steps_per_beat as f64)
+    }
+
+    pub fn period_ms(&self) -> f64 {
+        self.step_ms() * self.steps.len() as f64
+    }
+
+    /// One pass over the pattern.
+    pub fn timeline(&self, tuning: &Tuning) -> Timeline {
+        let step_ms = self.step_ms();
+        let note_length = (step_ms * self.gate as f64 / 1000.0) as f32;
+        let mut timeline = Timeline::new();
+
+        for (i, step) in self.steps.iter().enumerate() {
+            let sound = match step {
+                Step::Rest => continue,
+                Step::Hit => self.instrument.clone(),
+                Step::Note(midi) => self
+                    .instrument
+                    .clone()
+                    .with_freq(tuning.midi_to_freq(*midi)),
+            };
+            let swing = if i % 2 == 1 {
+                self.swing * step_ms / 3.0
+            } else {
+                0.0
+            };
+            timeline.push(
+                TimelineEntry::new(sound.with_note_length(note_length))
+                    .at(i as f64 * step_ms + swing)
+                    .with_gain(self.gain),
+            );
+        }
+        timeline
+    }
+
+    pub fn source(&self, transform: &Transform, tuning: &Tuning) -> Result<VoiceSource, String> {
+        let buffer = self.timeline(tuning).render(transform)?;
+        let period = mixer::ms_to_samples(self.period_ms() * transform.time_scale) as usize;
+        Ok(Box::new(LoopSource::new(buffer, period, self.loops)))
+    }
+}
+
+pub struct Sequence {
+    pub tracks: Vec<Track>,
+}
+
+impl Sequence {
+    pub fn sources(
+        &self,
+        transform: &Transform,
+        tuning: &Tuning,
+    ) -> Result<Vec<VoiceSource>, String> {
+        self.tracks
+            .iter()
+            .map(|track| track.source(transform, tuning))
+            .collect()
+    }
+}
+
+fn get_pattern(table: &LuaTable) -> LuaResult<Vec<Step>> {
+    let steps = match table.get::<LuaValue>("pattern")? {
+        LuaValue::String(s) => parse_pattern(&s.to_str()?),
+        LuaValue::Table(list) => list
+            .sequence_values::<LuaValue>()
+            .map(|value| match value? {
+                LuaValue::Boolean(false) => Ok(Step::Rest),
+                LuaValue::Boolean(true) => Ok(Step::Hit),
+                LuaValue::Integer(n) => Ok(Step::Note(n as f64)),
+                LuaValue::Number(n) => Ok(Step::Note(n)),
+                LuaValue::String(s) => Ok(Step::parse(&s.to_str()?)),
+                other => Ok(Err(format!("invalid step type {}", other.type_name()))),
+            })
+            .collect::<LuaResult<Result<Vec<_>, String>>>()?,
+        _ => Err("expected pattern string or list of steps".to_string()),
+    }
+    .map_err(|e| mlua::Error::RuntimeError(format!("Invalid pattern: {}", e)))?;
+
+    if steps.is_empty() {
+        return Err(mlua::Error::RuntimeError(
+            "Invalid pattern: no steps".into(),
+        ));
+    }
+    Ok(steps)
+}
+
+// Reads a track setting, falling back to the sequence-wide value
+fn setting<T: FromLua>(track: &LuaTable, sequence: &LuaTable, key: &str) -> LuaResult<Option<T>> {
+    match track.get::<Option<T>>(key)? {
+        Some(value) => Ok(Some(value)),
+        None => sequence.get::<Option<T>>(key),
+    }
+}
+
+impl FromLua for Sequence {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let LuaValue::Table(table) = value else {
+            return Err(mlua::Error::RuntimeError(
+                "Expected table for sequence".into(),
+            ));
+        };
+
+        let mut tracks = Vec::new();
+        for track_table in table
+            .get::<LuaTable>("tracks")?
+            .sequence_values::<LuaTable>()
+        {
+            let track_table = track_table?;
+            let mut track = Track::new(track_table.get("instrument")?, get_pattern(&track_table)?);
+
+            if let Some(bpm) = setting::<f64>(&track_table, &table, "bpm")? {
+                if !bpm.is_finite() || bpm <= 0.0 {
+                    return Err(mlua::Error::RuntimeError(format!("Invalid bpm: {}", bpm)));
+                }
+                track.bpm = bpm;
+            }
+            if let Some(steps_per_beat) = setting::<u32>(&track_table, &table, "steps_per_beat")? {
+                track.steps_per_beat = steps_per_beat.max(1);
+            }
+            if let Some(swing) = setting::<f64>(&track_table, &table, "swing")? {
+                track.swing = swing.clamp(0.0, 1.0);
+            }
+            if let Some(loops) = setting::<usize>(&track_table, &table, "loops")? {
+                track.loops = if loops == 0 { None } else { Some(loops) };
+            }
+            if let Some(gate) = setting::<f32>(&track_table, &table, "gate")? {
+                track.gate = gate.max(0.0);
+            }
+            if let Some(gain) = track_table.get::<Option<f32>>("gain")? {
+                track.gain = gain.max(0.0);
+            }
+            tracks.push(track);
+        }
+
+        Ok(Self { tracks })
+    }
+}
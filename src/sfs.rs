@@ -0,0 +1,164 @@
+// DrPetter's .sfs settings files: a version number, the wave type and the
+// parameters as native (little-endian) 32-bit values in the editor's order.
+// Version 101 added the delta slide and arpeggio, 102 the volume.
+
+use crate::sound::{self, SoundParams};
+use sfxr::Sample;
+use thiserror::Error;
+
+const VERSION: i32 = 102;
+// sfxr's volume for files written before 102
+const DEFAULT_VOLUME: f32 = 0.5;
+
+#[derive(Error, Debug)]
+pub enum SfsError {
+    #[error("Failed to access .sfs file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid .sfs file: {0}")]
+    Invalid(String),
+    #[error("Unsupported .sfs file: {0}")]
+    Unsupported(String),
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn word(&mut self) -> Result<[u8; 4], SfsError> {
+        let word = self
+            .data
+            .get(self.pos..self.pos + 4)
+            .ok_or_else(|| SfsError::Invalid("unexpected end of file".into()))?;
+        self.pos += 4;
+        Ok(word.try_into().unwrap())
+    }
+
+    fn int(&mut self) -> Result<i32, SfsError> {
+        Ok(i32::from_le_bytes(self.word()?))
+    }
+
+    fn float(&mut self) -> Result<f32, SfsError> {
+        Ok(f32::from_le_bytes(self.word()?))
+    }
+
+    // A C++ bool, one byte
+    fn skip_bool(&mut self) -> Result<(), SfsError> {
+        if self.pos >= self.data.len() {
+            return Err(SfsError::Invalid("unexpected end of file".into()));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+}
+
+pub fn parse(data: &[u8]) -> Result<SoundParams, SfsError> {
+    let mut reader = Reader { data, pos: 0 };
+    let version = reader.int()?;
+    if !(100..=102).contains(&version) {
+        return Err(SfsError::Unsupported(format!("version {}", version)));
+    }
+
+    let mut sample = Sample::new();
+    sample.wave_type = sound::wave_type(reader.int()? as i64).map_err(SfsError::Invalid)?;
+    let volume = if version >= 102 {
+        reader.float()?
+    } else {
+        DEFAULT_VOLUME
+    };
+
+    sample.base_freq = reader.float()? as f64;
+    sample.freq_limit = reader.float()? as f64;
+    sample.freq_ramp = reader.float()? as f64;
+    sample.freq_dramp = if version >= 101 {
+        reader.float()? as f64
+    } else {
+        0.0
+    };
+    sample.duty = reader.float()?;
+    sample.duty_ramp = reader.float()?;
+    sample.vib_strength = reader.float()? as f64;
+    sample.vib_speed = reader.float()? as f64;
+    // Vibrato delay, which sfxr never used
+    reader.float()?;
+    sample.env_attack = reader.float()?;
+    sample.env_sustain = reader.float()?;
+    sample.env_decay = reader.float()?;
+    sample.env_punch = reader.float()?;
+    // The filter switch, also unused: the filters are always on
+    reader.skip_bool()?;
+    sample.lpf_resonance = reader.float()?;
+    sample.lpf_freq = reader.float()?;
+    sample.lpf_ramp = reader.float()?;
+    sample.hpf_freq = reader.float()?;
+    sample.hpf_ramp = reader.float()?;
+    sample.pha_offset = reader.float()?;
+    sample.pha_ramp = reader.float()?;
+    sample.repeat_speed = reader.float()?;
+    if version >= 101 {
+        sample.arp_speed = reader.float()?;
+        sample.arp_mod = reader.float()? as f64;
+    }
+
+    Ok(SoundParams::new(sample).with_volume(volume.clamp(0.0, 1.0)))
+}
+
+pub fn load(path: &str) -> Result<SoundParams, SfsError> {
+    parse(&std::fs::read(path)?)
+}
+
+/// Serialize a plain sfxr sound in the version 102 layout.
+pub fn write(params: &SoundParams) -> Result<Vec<u8>, SfsError> {
+    if !params.is_sfxr() {
+        return Err(SfsError::Unsupported(
+            "only plain sfxr sounds can be saved".into(),
+        ));
+    }
+    let sample = params.sample();
+    let mut data = Vec::new();
+    data.extend_from_slice(&VERSION.to_le_bytes());
+    data.extend_from_slice(&(sound::wave_type_index(sample.wave_type) as i32).to_le_bytes());
+
+    let before_filter = [
+        params.volume(),
+        sample.base_freq as f32,
+        sample.freq_limit as f32,
+        sample.freq_ramp as f32,
+        sample.freq_dramp as f32,
+        sample.duty,
+        sample.duty_ramp,
+        sample.vib_strength as f32,
+        sample.vib_speed as f32,
+        0.0,
+        sample.env_attack,
+        sample.env_sustain,
+        sample.env_decay,
+        sample.env_punch,
+    ];
+    let after_filter = [
+        sample.lpf_resonance,
+        sample.lpf_freq,
+        sample.lpf_ramp,
+        sample.hpf_freq,
+        sample.hpf_ramp,
+        sample.pha_offset,
+        sample.pha_ramp,
+        sample.repeat_speed,
+        sample.arp_speed,
+        sample.arp_mod as f32,
+    ];
+    for value in before_filter {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.push(1);
+    for value in after_filter {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    Ok(data)
+}
+
+pub fn save(path: &str, params: &SoundParams) -> Result<(), SfsError> {
+    std::fs::write(path, write(params)?)?;
+    Ok(())
+}
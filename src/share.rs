@@ -0,0 +1,157 @@
+// sfxr.me share links: the wave type byte followed by each parameter as a
+// little-endian f32, in jsfxr's order, base58 encoded after the '#'.
+
+use crate::sound::{self, SoundParams};
+use sfxr::Sample;
+
+pub const URL: &str = "https://sfxr.me/#";
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const PARAMS: usize = 22;
+
+// The hash of a share link, or `text` itself when it is a bare hash
+fn fragment(text: &str) -> &str {
+    let text = text.trim();
+    match text.rsplit_once('#') {
+        Some((_, hash)) => hash,
+        None => text,
+    }
+}
+
+/// Whether `text` is a share link, its `#` fragment or a bare base58 hash.
+pub fn is_share_link(text: &str) -> bool {
+    let hash = fragment(text);
+    !hash.is_empty() && hash.bytes().all(|byte| ALPHABET.contains(&byte))
+}
+
+fn base58_decode(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in text.bytes() {
+        let mut carry = ALPHABET
+            .iter()
+            .position(|&a| a == c)
+            .ok_or_else(|| format!("Invalid base58 character '{}'", c as char))?
+            as u32;
+        for byte in bytes.iter_mut() {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    // Each leading '1' stands for a leading zero byte
+    let zeros = text.bytes().take_while(|&c| c == ALPHABET[0]).count();
+    bytes.extend(std::iter::repeat_n(0, zeros));
+    bytes.reverse();
+    Ok(bytes)
+}
+
+fn base58_encode(bytes: &[u8]) -> String {
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in bytes {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let zeros = bytes.iter().take_while(|&&byte| byte == 0).count();
+    std::iter::repeat_n(ALPHABET[0], zeros)
+        .chain(digits.iter().rev().map(|&digit| ALPHABET[digit as usize]))
+        .map(char::from)
+        .collect()
+}
+
+// Parameters in serialisation order
+fn fields(sample: &Sample) -> [f32; PARAMS] {
+    [
+        sample.env_attack,
+        sample.env_sustain,
+        sample.env_punch,
+        sample.env_decay,
+        sample.base_freq as f32,
+        sample.freq_limit as f32,
+        sample.freq_ramp as f32,
+        sample.freq_dramp as f32,
+        sample.vib_strength as f32,
+        sample.vib_speed as f32,
+        sample.arp_mod as f32,
+        sample.arp_speed,
+        sample.duty,
+        sample.duty_ramp,
+        sample.repeat_speed,
+        sample.pha_offset,
+        sample.pha_ramp,
+        sample.lpf_freq,
+        sample.lpf_ramp,
+        sample.lpf_resonance,
+        sample.hpf_freq,
+        sample.hpf_ramp,
+    ]
+}
+
+/// Decode a share link, its fragment or a bare hash. Links carry no volume, so the
+/// default is used.
+pub fn decode(text: &str) -> Result<SoundParams, String> {
+    let bytes = base58_decode(fragment(text))?;
+    if bytes.len() < 1 + PARAMS * 4 {
+        return Err(format!(
+            "Invalid share link: expected {} bytes, got {}",
+            1 + PARAMS * 4,
+            bytes.len()
+        ));
+    }
+
+    let mut p = [0.0f32; PARAMS];
+    for (i, value) in p.iter_mut().enumerate() {
+        let offset = 1 + i * 4;
+        let word: [u8; 4] = bytes[offset..offset + 4].try_into().unwrap();
+        *value = f32::from_le_bytes(word);
+    }
+
+    let mut sample = Sample::new();
+    sample.wave_type = sound::wave_type(bytes[0] as i64)?;
+    sample.env_attack = p[0];
+    sample.env_sustain = p[1];
+    sample.env_punch = p[2];
+    sample.env_decay = p[3];
+    sample.base_freq = p[4] as f64;
+    sample.freq_limit = p[5] as f64;
+    sample.freq_ramp = p[6] as f64;
+    sample.freq_dramp = p[7] as f64;
+    sample.vib_strength = p[8] as f64;
+    sample.vib_speed = p[9] as f64;
+    sample.arp_mod = p[10] as f64;
+    sample.arp_speed = p[11];
+    sample.duty = p[12];
+    sample.duty_ramp = p[13];
+    sample.repeat_speed = p[14];
+    sample.pha_offset = p[15];
+    sample.pha_ramp = p[16];
+    sample.lpf_freq = p[17];
+    sample.lpf_ramp = p[18];
+    sample.lpf_resonance = p[19];
+    sample.hpf_freq = p[20];
+    sample.hpf_ramp = p[21];
+    Ok(SoundParams::new(sample))
+}
+
+/// Encode a plain sfxr sound as a full share link.
+pub fn encode(params: &SoundParams) -> Result<String, String> {
+    if !params.is_sfxr() {
+        return Err("Only plain sfxr sounds can be shared on sfxr.me".into());
+    }
+    let sample = params.sample();
+    let mut bytes = vec![sound::wave_type_index(sample.wave_type)];
+    for value in fields(sample) {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    Ok(format!("{}{}", URL, base58_encode(&bytes)))
+}
@@ -1,9 +1,18 @@
+use crate::bfxr;
+use crate::mixer;
+use crate::pitch;
+use crate::player::Player;
+use crate::share;
+use crate::synth::{self, Extended, Waveform};
+use crate::tuning::Tuning;
+use crate::units::{self, env_to_seconds, freq_to_param, param_to_freq, seconds_to_env};
+use crate::voice::{Engine, Glide};
 use mlua::prelude::*;
 use serde::{Deserialize, Serialize};
 use sfxr::{Generator, Sample, WaveType};
 use std::sync::Arc;
 
-// Used to parse json values from [jsfxr](https://sfxr.me/)
+// Used to read and write json values from [jsfxr](https://sfxr.me/)
 #[derive(Serialize, Deserialize)]
 #[serde(default)]
 struct JsonParams {
@@ -31,6 +40,11 @@ struct JsonParams {
     p_hpf_freq: f32,
     p_hpf_ramp: f32,
     sound_vol: f32,
+    // Tells jsfxr the values are in sfxr's units rather than its editor's
+    #[serde(rename = "oldParams")]
+    old_params: bool,
+    sample_rate: u32,
+    sample_size: u8,
 }
 
 impl Default for JsonParams {
@@ -60,6 +74,9 @@ impl Default for JsonParams {
             p_hpf_freq: 0.0,
             p_hpf_ramp: 0.0,
             sound_vol: 0.2,
+            old_params: true,
+            sample_rate: 44100,
+            sample_size: 8,
         }
     }
 }
@@ -92,17 +109,116 @@ struct SoundConfig {
     arp_mod: Option<f64>,
 }
 
+/// Global adjustments applied to every sound when it is rendered.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    /// Pitch offset in semitones, fractional values are cents.
+    pub transpose: f64,
+    /// Duration multiplier, values below 1.0 make sounds snappier.
+    pub time_scale: f64,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            transpose: 0.0,
+            time_scale: 1.0,
+        }
+    }
+}
+
+impl Transform {
+    pub fn is_identity(&self) -> bool {
+        self.transpose == 0.0 && self.time_scale == 1.0
+    }
+}
+
+fn transpose_freq(v: f64, ratio: f64) -> f64 {
+    freq_to_param(param_to_freq(v) * ratio)
+}
+
+fn scale_envelope(v: f32, factor: f64) -> f32 {
+    (v as f64 * factor.sqrt()).clamp(0.0, 1.0) as f32
+}
+
+// Filter cutoffs are multiplied by 1 + value * step every sample
+fn scale_ramp(v: f32, step: f64, factor: f64) -> f32 {
+    let per_sample = (1.0 + v as f64 * step).max(0.0).powf(1.0 / factor);
+    ((per_sample - 1.0) / step).clamp(-1.0, 1.0) as f32
+}
+
+// Arpeggio and repeat periods are (1 - value)^2 * 20000 + 32 samples
+fn scale_period(v: f32, factor: f64) -> f32 {
+    let limit = (1.0 - v as f64).powi(2) * 20000.0 + 32.0;
+    let scaled = ((limit * factor - 32.0).max(0.0) / 20000.0).sqrt();
+    (1.0 - scaled).clamp(0.0, 1.0) as f32
+}
+
+/// sfxr wave type for an index 0-4.
+pub fn wave_type(index: i64) -> Result<WaveType, String> {
+    Waveform::from_index(index)?
+        .sfxr()
+        .ok_or_else(|| format!("Invalid wave_type: {} (expected 0-4)", index))
+}
+
+pub fn wave_type_index(wave_type: WaveType) -> u8 {
+    match wave_type {
+        WaveType::Square => 0,
+        WaveType::Sawtooth => 1,
+        WaveType::Sine => 2,
+        WaveType::Noise => 3,
+        WaveType::Triangle => 4,
+    }
+}
+
+// sfxr wave types go in the sample, bfxr ones replace its oscillator
+fn apply_wave_type(index: i64, sample: &mut Sample, extended: &mut Extended) -> LuaResult<()> {
+    let waveform = Waveform::from_index(index).map_err(mlua::Error::RuntimeError)?;
+    match waveform.sfxr() {
+        Some(wave_type) => sample.wave_type = wave_type,
+        None => extended.waveform = Some(waveform),
+    }
+    Ok(())
+}
+
+// Frequencies are given in Hz, or as a note name / MIDI note number string
+pub fn get_freq(table: &LuaTable, key: &str, tuning: &Tuning) -> LuaResult<Option<f64>> {
+    match table.get::<LuaValue>(key)? {
+        LuaValue::Integer(v) => Ok(Some(v as f64)),
+        LuaValue::Number(v) => Ok(Some(v)),
+        LuaValue::String(s) => pitch::note_to_freq(&s.to_str()?, tuning)
+            .map(Some)
+            .map_err(|e| mlua::Error::RuntimeError(format!("Invalid {}: {}", key, e))),
+        _ => Ok(None),
+    }
+}
+
 #[derive(Clone)]
 pub struct SoundParams {
     sample: Arc<Sample>,
+    /// Renders instead of the sfxr sample when set.
+    engine: Option<Engine>,
+    /// bfxr waveforms and effects; rendered natively instead of by the sfxr crate when set.
+    extended: Extended,
     volume: f32,
+    glide_ms: f32,
 }
 
 impl SoundParams {
     pub fn new(sample: Sample) -> Self {
         Self {
             sample: Arc::new(sample),
+            engine: None,
+            extended: Extended::default(),
             volume: 0.2,
+            glide_ms: 0.0,
+        }
+    }
+
+    pub fn from_engine(engine: Engine) -> Self {
+        Self {
+            engine: Some(engine),
+            ..Self::new(Sample::new())
         }
     }
 
@@ -111,173 +227,359 @@ impl SoundParams {
         self
     }
 
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// Glide from the previous note's pitch over `ms` when sequenced after another note.
+    pub fn with_glide(mut self, ms: f32) -> Self {
+        self.glide_ms = ms.max(0.0);
+        self
+    }
+
+    pub fn glide_ms(&self) -> f32 {
+        self.glide_ms
+    }
+
+    /// Play a single-cycle waveform instead of `wave_type`, keeping the sfxr envelope,
+    /// filters and slides. Values are rescaled to fill -1.0..1.0.
+    pub fn with_wavetable(mut self, values: &[f32]) -> Self {
+        self.extended.waveform = Some(Waveform::Table(synth::normalize_wavetable(values).into()));
+        self
+    }
+
+    pub fn with_extended(mut self, extended: Extended) -> Self {
+        self.extended = extended;
+        self
+    }
+
+    pub fn freq(&self) -> f64 {
+        match &self.engine {
+            Some(engine) => engine.voice().freq(),
+            None => param_to_freq(self.sample.base_freq),
+        }
+    }
+
+    pub fn sample(&self) -> &Sample {
+        &self.sample
+    }
+
+    pub fn extended(&self) -> &Extended {
+        &self.extended
+    }
+
+    /// Whether the sound is plain sfxr, without another engine or bfxr extensions.
+    pub fn is_sfxr(&self) -> bool {
+        self.engine.is_none() && self.extended.is_plain()
+    }
+
+    /// Serialize to the JSON sfxr.me exports and imports. Only plain sfxr sounds can be
+    /// represented.
+    pub fn to_json(&self) -> Result<String, String> {
+        if !self.is_sfxr() {
+            return Err("Only plain sfxr sounds can be exported to JSON".into());
+        }
+        let sample = &self.sample;
+        let json = JsonParams {
+            wave_type: wave_type_index(sample.wave_type),
+            p_env_attack: sample.env_attack,
+            p_env_sustain: sample.env_sustain,
+            p_env_decay: sample.env_decay,
+            p_env_punch: sample.env_punch,
+            p_base_freq: sample.base_freq,
+            p_freq_limit: sample.freq_limit,
+            p_freq_ramp: sample.freq_ramp,
+            p_freq_dramp: sample.freq_dramp,
+            p_vib_strength: sample.vib_strength,
+            p_vib_speed: sample.vib_speed,
+            p_arp_speed: sample.arp_speed,
+            p_arp_mod: sample.arp_mod,
+            p_duty: sample.duty,
+            p_duty_ramp: sample.duty_ramp,
+            p_repeat_speed: sample.repeat_speed,
+            p_pha_offset: sample.pha_offset,
+            p_pha_ramp: sample.pha_ramp,
+            p_lpf_freq: sample.lpf_freq,
+            p_lpf_ramp: sample.lpf_ramp,
+            p_lpf_resonance: sample.lpf_resonance,
+            p_hpf_freq: sample.hpf_freq,
+            p_hpf_ramp: sample.hpf_ramp,
+            sound_vol: self.volume,
+            ..JsonParams::default()
+        };
+        serde_json::to_string(&json).map_err(|e| e.to_string())
+    }
+
     pub fn generator(&self) -> Generator {
         let mut gen = Generator::new(*self.sample.as_ref());
         gen.volume = self.volume;
         gen
     }
 
-    pub fn from_table(table: LuaTable) -> LuaResult<SoundParams> {
+    /// Same timbre at a different pitch.
+    pub fn with_freq(mut self, hz: f64) -> Self {
+        match &mut self.engine {
+            Some(engine) => engine.voice_mut().set_freq(hz),
+            None => Arc::make_mut(&mut self.sample).base_freq = freq_to_param(hz),
+        }
+        self
+    }
+
+    /// Fit the sustain stage so attack, sustain and decay last `seconds` in total.
+    /// Attack and decay are kept, so notes shorter than both still ring out.
+    pub fn with_note_length(mut self, seconds: f32) -> Self {
+        if let Some(engine) = &mut self.engine {
+            engine.voice_mut().set_note_length(seconds);
+            return self;
+        }
+        let sample = Arc::make_mut(&mut self.sample);
+        let sustain =
+            seconds - env_to_seconds(sample.env_attack) - env_to_seconds(sample.env_decay);
+        sample.env_sustain = seconds_to_env(sustain);
+        self
+    }
+
+    pub fn transformed(&self, transform: &Transform) -> SoundParams {
+        if transform.is_identity() {
+            return self.clone();
+        }
+
+        if let Some(engine) = &self.engine {
+            let mut engine = engine.clone();
+            let voice = engine.voice_mut();
+            voice.set_freq(voice.freq() * (transform.transpose / 12.0).exp2());
+            voice.scale_time(transform.time_scale);
+            return Self {
+                engine: Some(engine),
+                glide_ms: self.glide_ms * transform.time_scale as f32,
+                ..self.clone()
+            };
+        }
+
+        let mut sample = *self.sample.as_ref();
+        let ratio = (transform.transpose / 12.0).exp2();
+        let factor = transform.time_scale;
+
+        if transform.transpose != 0.0 {
+            sample.base_freq = transpose_freq(sample.base_freq, ratio);
+            if sample.freq_limit > 0.0 {
+                sample.freq_limit = transpose_freq(sample.freq_limit, ratio);
+            }
+        }
+
+        if factor != 1.0 {
+            sample.env_attack = scale_envelope(sample.env_attack, factor);
+            sample.env_sustain = scale_envelope(sample.env_sustain, factor);
+            sample.env_decay = scale_envelope(sample.env_decay, factor);
+            // 1.0 disables the arpeggio and 0.0 disables repeat
+            if sample.arp_speed < 1.0 {
+                sample.arp_speed = scale_period(sample.arp_speed, factor);
+            }
+            if sample.repeat_speed > 0.0 {
+                sample.repeat_speed = scale_period(sample.repeat_speed, factor);
+            }
+            sample.vib_speed = (sample.vib_speed / factor.sqrt()).clamp(0.0, 1.0);
+            if sample.freq_ramp != 0.0 {
+                let slide = 1.0 - sample.freq_ramp.powi(3) * 0.01;
+                sample.freq_ramp = ((1.0 - slide.max(0.0).powf(1.0 / factor)) / 0.01)
+                    .cbrt()
+                    .clamp(-1.0, 1.0);
+            }
+            // The slide changes by freq_dramp^3 every sample, a rate of a rate
+            sample.freq_dramp = (sample.freq_dramp / factor.powf(2.0 / 3.0)).clamp(-1.0, 1.0);
+            // Duty and phaser offsets move by a fixed step every sample
+            sample.duty_ramp = (sample.duty_ramp as f64 / factor).clamp(-1.0, 1.0) as f32;
+            sample.pha_ramp = (sample.pha_ramp as f64 / factor.sqrt()).clamp(-1.0, 1.0) as f32;
+            sample.lpf_ramp = scale_ramp(sample.lpf_ramp, 0.0001, factor);
+            sample.hpf_ramp = scale_ramp(sample.hpf_ramp, 0.0003, factor);
+        }
+
+        // The bit crush sweep is per second too
+        let extended = Extended {
+            bit_crush_sweep: self.extended.bit_crush_sweep / factor as f32,
+            ..self.extended.clone()
+        };
+
+        Self {
+            sample: Arc::new(sample),
+            extended,
+            glide_ms: self.glide_ms * factor as f32,
+            ..self.clone()
+        }
+    }
+
+    pub fn duration_samples(&self) -> usize {
+        if let Some(engine) = &self.engine {
+            return engine.voice().duration_samples();
+        }
+        let total_duration = (self.sample.env_attack.powi(2)
+            + self.sample.env_sustain.powi(2)
+            + self.sample.env_decay.powi(2))
+            * 100000.0;
+        total_duration.ceil() as usize
+    }
+
+    pub fn render(&self) -> Vec<f32> {
+        self.render_with(&Glide::NONE)
+    }
+
+    /// Render with the pitch bending from `from_hz` to this sound's own over its glide
+    /// time. The envelope and length are those of a plain render.
+    pub fn render_glide(&self, from_hz: f64) -> Vec<f32> {
+        let samples = mixer::ms_to_samples(self.glide_ms as f64) as usize;
+        self.render_with(&Glide::new(from_hz, self.freq(), samples))
+    }
+
+    fn render_with(&self, glide: &Glide) -> Vec<f32> {
+        if let Some(engine) = &self.engine {
+            let mut buffer = engine.voice().render_glide(glide);
+            for sample in buffer.iter_mut() {
+                *sample *= self.volume;
+            }
+            return buffer;
+        }
+        // The sfxr crate has no way to bend its pitch, so gliding sounds go through the
+        // port, every note of them, so one that starts a run sounds like the rest
+        if !self.extended.is_plain() || self.glide_ms > 0.0 {
+            return synth::render(
+                &self.sample,
+                &self.extended,
+                glide,
+                self.volume,
+                self.duration_samples(),
+            );
+        }
+        let mut generator = self.generator();
+        let mut buffer = vec![0.0; self.duration_samples()];
+        generator.generate(&mut buffer);
+        buffer
+    }
+
+    pub fn from_table(table: LuaTable, tuning: &Tuning) -> LuaResult<SoundParams> {
         let mut sample = Sample::new();
         let mut volume = 0.2;
+        let mut glide_ms = 0.0;
+        let engine = match table.get::<Option<String>>("engine")? {
+            Some(name) if name != "sfxr" => Some(Engine::from_table(&name, &table, tuning)?),
+            _ => None,
+        };
 
-        if let Ok(wave_type) = table.get("wave_type") {
-            sample.wave_type = match wave_type {
-                0 => WaveType::Square,
-                1 => WaveType::Sawtooth,
-                2 => WaveType::Sine,
-                3 => WaveType::Noise,
-                4 => WaveType::Triangle,
-                _ => WaveType::Square,
-            };
+        let mut extended = Extended::default();
+        if let Some(value) = table.get::<Option<i64>>("wave_type")? {
+            apply_wave_type(value, &mut sample, &mut extended)?;
+        }
+        if let Some(values) = table.get::<Option<Vec<f32>>>("wavetable")? {
+            if values.len() < 2 {
+                return Err(mlua::Error::RuntimeError(
+                    "Invalid wavetable: expected at least 2 values".into(),
+                ));
+            }
+            extended.waveform = Some(Waveform::Table(synth::normalize_wavetable(&values).into()));
+        }
+        if let Ok(v) = table.get::<u32>("overtones") {
+            extended.overtones = v.min(10);
+        }
+        if let Ok(v) = table.get::<f32>("overtone_falloff") {
+            extended.overtone_falloff = v.clamp(0.0, 1.0);
+        }
+        if let Ok(v) = table.get::<f32>("bit_crush") {
+            extended.bit_crush = v.clamp(0.0, 1.0);
+        }
+        if let Ok(v) = table.get::<f32>("bit_crush_sweep") {
+            extended.bit_crush_sweep = v;
+        }
+        if let Ok(v) = table.get::<f32>("compression") {
+            extended.compression = v.clamp(0.0, 1.0);
         }
         if let Ok(v) = table.get::<f32>("env_attack") {
-            sample.env_attack = ((v.max(0.0) * 44100.0) / 100000.0).sqrt().clamp(0.0, 1.0);
+            sample.env_attack = seconds_to_env(v);
         }
         if let Ok(v) = table.get::<f32>("env_sustain") {
-            sample.env_sustain = ((v.max(0.0) * 44100.0) / 100000.0).sqrt().clamp(0.0, 1.0);
+            sample.env_sustain = seconds_to_env(v);
         }
-        if let Ok(v) = table.get::<f32>("env_punch") {
-            sample.env_punch = (v / 100.0).clamp(-1.0, 1.0);
+        if let Ok(v) = table.get::<f64>("env_punch") {
+            sample.env_punch = units::punch_to_param(v) as f32;
         }
         if let Ok(v) = table.get::<f32>("env_decay") {
-            sample.env_decay = ((v.max(0.0) * 44100.0) / 100000.0).sqrt().clamp(0.0, 1.0);
+            sample.env_decay = seconds_to_env(v);
         }
 
-        if let Ok(v) = table.get::<f64>("base_freq") {
-            sample.base_freq = (v * 100.0 / (8.0 * 44100.0) - 0.001)
-                .max(0.0)
-                .sqrt()
-                .clamp(0.0, 1.0);
+        if let Some(v) = get_freq(&table, "base_freq", tuning)? {
+            sample.base_freq = freq_to_param(v);
         }
-        if let Ok(v) = table.get::<f64>("freq_limit") {
-            sample.freq_limit = (v * 100.0 / (8.0 * 44100.0) - 0.001)
-                .max(0.0)
-                .sqrt()
-                .clamp(0.0, 1.0);
+        if let Some(v) = get_freq(&table, "freq_limit", tuning)? {
+            sample.freq_limit = freq_to_param(v);
         }
         if let Ok(v) = table.get::<f64>("freq_ramp") {
-            sample.freq_ramp = if v == 0.0 {
-                0.0
-            } else {
-                (1.0 - (-v / 44100.0 * std::f64::consts::LN_2).exp() / 0.01)
-                    .cbrt()
-                    .clamp(-1.0, 1.0)
-            }
+            sample.freq_ramp = units::freq_ramp_to_param(v);
         }
         if let Ok(v) = table.get::<f64>("freq_dramp") {
-            sample.freq_dramp = (v * (-44101.0_f64 / 44100.0).exp2() / 44100.0 / -0.000001)
-                .cbrt()
-                .clamp(-1.0, 1.0);
+            sample.freq_dramp = units::freq_dramp_to_param(v);
         }
 
         if let Ok(v) = table.get::<f64>("vib_speed") {
-            sample.vib_speed = ((64.0 / 441000.0) * v.max(0.0) / 0.01)
-                .sqrt()
-                .clamp(0.0, 1.0);
+            sample.vib_speed = units::vib_speed_to_param(v);
         }
         if let Ok(v) = table.get::<f64>("vib_strength") {
-            sample.vib_strength = ((v / 100.0) / 0.5).clamp(0.0, 1.0);
-        }
-
-        if let Ok(mut v) = table.get::<f64>("arp_mod") {
-            if v == 0.0 {
-                sample.arp_mod = 0.0;
-            } else {
-                v = if (1.0 / v) < 1.0 {
-                    ((1.0 - (1.0 / v)) / 0.9).sqrt()
-                } else {
-                    -((1.0 / v - 1.0) / 10.0).sqrt()
-                };
-                sample.arp_mod = v.clamp(-1.0, 1.0);
-            }
+            sample.vib_strength = units::vib_strength_to_param(v);
         }
-        if let Ok(v) = table.get::<f32>("arp_speed") {
-            sample.arp_speed = if v == 0.0 {
-                1.0
-            } else {
-                (1.0 - ((v * 44100.0 - if v * 44100.0 < 100.0 { 30.0 } else { 32.0 }) / 20000.0)
-                    .sqrt())
-                .clamp(0.0, 1.0)
-            };
+
+        if let Ok(v) = table.get::<f64>("arp_mod") {
+            sample.arp_mod = units::arp_mod_to_param(v);
+        }
+        if let Ok(v) = table.get::<f64>("arp_speed") {
+            sample.arp_speed = units::arp_speed_to_param(v) as f32;
         }
 
-        if let Ok(v) = table.get::<f32>("duty") {
-            sample.duty = ((0.5 - (v / 100.0)) / 0.5).clamp(0.0, 1.0)
+        if let Ok(v) = table.get::<f64>("duty") {
+            sample.duty = units::duty_to_param(v) as f32;
         }
-        if let Ok(v) = table.get::<f32>("duty_ramp") {
-            sample.duty_ramp = ((v / (8.0 * 44100.0)) / -0.00005).clamp(-1.0, 1.0);
+        if let Ok(v) = table.get::<f64>("duty_ramp") {
+            sample.duty_ramp = units::duty_ramp_to_param(v) as f32;
         }
 
-        if let Ok(v) = table.get::<f32>("repeat_speed") {
-            let coverted = if v <= 0.0 {
-                0.0
-            } else if v > 1378.0 {
-                1.0
-            } else {
-                1.0 - (((44100.0 / v) - 32.0) / 20000.0).sqrt()
-            };
-            sample.repeat_speed = coverted.clamp(0.0, 1.0);
+        if let Ok(v) = table.get::<f64>("repeat_speed") {
+            sample.repeat_speed = units::repeat_speed_to_param(v) as f32;
         }
 
-        if let Ok(v) = table.get::<f32>("pha_offset") {
-            let converted = {
-                let sign = if v < 0.0 { -1.0 } else { 1.0 };
-                sign * (v.abs() / 1020.0).sqrt()
-            };
-            sample.pha_offset = converted.clamp(-1.0, 1.0);
+        if let Ok(v) = table.get::<f64>("pha_offset") {
+            sample.pha_offset = units::pha_offset_to_param(v) as f32;
         }
-        if let Ok(v) = table.get::<f32>("pha_ramp") {
-            let converted = {
-                let sign = if v < 0.0 { -1.0 } else { 1.0 };
-                sign * v.abs().sqrt()
-            };
-            sample.pha_ramp = converted.clamp(-1.0, 1.0);
+        if let Ok(v) = table.get::<f64>("pha_ramp") {
+            sample.pha_ramp = units::pha_ramp_to_param(v) as f32;
         }
 
-        if let Ok(v) = table.get::<f32>("lpf_freq") {
-            sample.lpf_freq = (v / (v + 8.0 * 44100.0) / 0.1).cbrt().clamp(0.0, 1.0);
+        if let Ok(v) = table.get::<f64>("lpf_freq") {
+            sample.lpf_freq = units::lpf_freq_to_param(v) as f32;
         }
-        if let Ok(v) = table.get::<f32>("lpf_ramp") {
-            let converted = if v == 0.0 {
-                0.0
-            } else {
-                (v.powf(1.0 / 44100.0) - 1.0) / 0.0001
-            };
-            sample.lpf_ramp = converted.clamp(-1.0, 1.0);
-        }
-        if let Ok(v) = table.get::<f32>("lpf_resonance") {
-            let inner = (((1.0 - v / 100.0) / 0.11) / 5.0).max(0.0);
-            let converted = if inner <= f32::EPSILON {
-                0.0
-            } else {
-                let value = (1.0 / inner - 1.0) / 20.0;
-                if value >= 0.0 {
-                    value.sqrt()
-                } else {
-                    0.0
-                }
-            };
-            sample.lpf_resonance = converted.clamp(0.0, 1.0);
+        if let Ok(v) = table.get::<f64>("lpf_ramp") {
+            sample.lpf_ramp = units::lpf_ramp_to_param(v) as f32;
+        }
+        if let Ok(v) = table.get::<f64>("lpf_resonance") {
+            sample.lpf_resonance = units::lpf_resonance_to_param(v) as f32;
         }
 
-        if let Ok(v) = table.get::<f32>("hpf_freq") {
-            sample.hpf_freq = (v / (v + 8.0 * 44100.0) / 0.1).sqrt().clamp(0.0, 1.0);
+        if let Ok(v) = table.get::<f64>("hpf_freq") {
+            sample.hpf_freq = units::hpf_freq_to_param(v) as f32;
         }
-        if let Ok(v) = table.get::<f32>("hpf_ramp") {
-            let converted = if v == 0.0 {
-                0.0
-            } else {
-                (v.powf(1.0 / 44100.0) - 1.0) / 0.0003
-            };
-            sample.hpf_ramp = converted.clamp(-1.0, 1.0);
+        if let Ok(v) = table.get::<f64>("hpf_ramp") {
+            sample.hpf_ramp = units::hpf_ramp_to_param(v) as f32;
         }
         if let Ok(v) = table.get::<f32>("sound_vol") {
             // volume = (((10_f32.powf(v / 10.0)).sqrt() + 1.0).ln()).clamp(0.0, 1.0);
             volume = v.clamp(0.0, 1.0);
         }
+        if let Ok(v) = table.get::<f32>("glide") {
+            glide_ms = v;
+        }
 
-        Ok(SoundParams::new(sample).with_volume(volume))
+        Ok(SoundParams {
+            engine,
+            extended,
+            ..SoundParams::new(sample)
+        }
+        .with_volume(volume)
+        .with_glide(glide_ms))
     }
 
     pub fn from_json(json_str: &str) -> LuaResult<SoundParams> {
@@ -285,15 +587,9 @@ impl SoundParams {
             .map_err(|e| mlua::Error::RuntimeError(format!("Invalid JSON: {}", e)))?;
 
         let mut sample = Sample::new();
+        let mut extended = Extended::default();
 
-        sample.wave_type = match json.wave_type {
-            0 => WaveType::Square,
-            1 => WaveType::Sawtooth,
-            2 => WaveType::Sine,
-            3 => WaveType::Noise,
-            4 => WaveType::Triangle,
-            _ => WaveType::Square,
-        };
+        apply_wave_type(json.wave_type as i64, &mut sample, &mut extended)?;
         sample.base_freq = json.p_base_freq;
         sample.freq_limit = json.p_freq_limit;
         sample.freq_ramp = json.p_freq_ramp;
@@ -317,24 +613,123 @@ impl SoundParams {
         sample.arp_speed = json.p_arp_speed;
         sample.arp_mod = json.p_arp_mod;
 
-        Ok(SoundParams::new(sample).with_volume(json.sound_vol))
+        Ok(SoundParams::new(sample)
+            .with_volume(json.sound_vol)
+            .with_extended(extended))
+    }
+}
+
+// Note names follow the tuning of the player the Lua module was created with
+fn lua_tuning(lua: &Lua) -> LuaResult<Tuning> {
+    match lua.app_data_ref::<Arc<Player>>() {
+        Some(player) => player
+            .tuning()
+            .map_err(|e| mlua::Error::external(e.to_string())),
+        None => Ok(Tuning::default()),
     }
 }
 
 impl FromLua for SoundParams {
-    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
         match value {
             LuaValue::Table(table) => {
-                let sample = Self::from_table(table)?;
+                let sample = Self::from_table(table, &lua_tuning(lua)?)?;
                 Ok(sample)
             }
             LuaValue::String(s) => {
-                let sample = Self::from_json(&s.to_str()?)?;
+                let text = s.to_str()?;
+                if bfxr::is_bfxr(&text) {
+                    let (sample, _) = bfxr::parse(&text).map_err(mlua::Error::RuntimeError)?;
+                    return Ok(sample);
+                }
+                if share::is_share_link(&text) {
+                    return share::decode(&text).map_err(mlua::Error::RuntimeError);
+                }
+                let sample = Self::from_json(&text)?;
                 Ok(sample)
             }
             _ => Err(mlua::Error::RuntimeError(
-                "Expected table or string for SoundParams".into(),
+                "Expected table, JSON, bfxr string or sfxr.me link for SoundParams".into(),
             )),
         }
     }
 }
+
+/// The inverse of `from_table`: the keys it accepts, in the same units.
+impl IntoLua for SoundParams {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("sound_vol", self.volume)?;
+        if self.glide_ms > 0.0 {
+            table.set("glide", self.glide_ms)?;
+        }
+        if let Some(engine) = &self.engine {
+            engine.to_table(lua, &table)?;
+            return Ok(LuaValue::Table(table));
+        }
+
+        let sample = &self.sample;
+        match &self.extended.waveform {
+            Some(Waveform::Table(values)) => table.set("wavetable", values.to_vec())?,
+            Some(waveform) => table.set("wave_type", waveform.index())?,
+            None => table.set("wave_type", wave_type_index(sample.wave_type))?,
+        }
+        table.set("env_attack", env_to_seconds(sample.env_attack))?;
+        table.set("env_sustain", env_to_seconds(sample.env_sustain))?;
+        table.set("env_punch", units::param_to_punch(sample.env_punch as f64))?;
+        table.set("env_decay", env_to_seconds(sample.env_decay))?;
+        table.set("base_freq", param_to_freq(sample.base_freq))?;
+        // 0 is no limit rather than the lowest frequency
+        if sample.freq_limit > 0.0 {
+            table.set("freq_limit", param_to_freq(sample.freq_limit))?;
+        }
+        table.set("freq_ramp", units::param_to_freq_ramp(sample.freq_ramp))?;
+        table.set("freq_dramp", units::param_to_freq_dramp(sample.freq_dramp))?;
+        table.set("vib_speed", units::param_to_vib_speed(sample.vib_speed))?;
+        table.set(
+            "vib_strength",
+            units::param_to_vib_strength(sample.vib_strength),
+        )?;
+        table.set("arp_mod", units::param_to_arp_mod(sample.arp_mod))?;
+        table.set(
+            "arp_speed",
+            units::param_to_arp_speed(sample.arp_speed as f64),
+        )?;
+        table.set("duty", units::param_to_duty(sample.duty as f64))?;
+        table.set(
+            "duty_ramp",
+            units::param_to_duty_ramp(sample.duty_ramp as f64),
+        )?;
+        table.set(
+            "repeat_speed",
+            units::param_to_repeat_speed(sample.repeat_speed as f64),
+        )?;
+        table.set(
+            "pha_offset",
+            units::param_to_pha_offset(sample.pha_offset as f64),
+        )?;
+        table.set("pha_ramp", units::param_to_pha_ramp(sample.pha_ramp as f64))?;
+        table.set("lpf_freq", units::param_to_lpf_freq(sample.lpf_freq as f64))?;
+        table.set("lpf_ramp", units::param_to_lpf_ramp(sample.lpf_ramp as f64))?;
+        table.set(
+            "lpf_resonance",
+            units::param_to_lpf_resonance(sample.lpf_resonance as f64),
+        )?;
+        table.set("hpf_freq", units::param_to_hpf_freq(sample.hpf_freq as f64))?;
+        table.set("hpf_ramp", units::param_to_hpf_ramp(sample.hpf_ramp as f64))?;
+
+        let extended = &self.extended;
+        if extended.overtones > 0 {
+            table.set("overtones", extended.overtones)?;
+            table.set("overtone_falloff", extended.overtone_falloff)?;
+        }
+        if extended.bit_crush > 0.0 || extended.bit_crush_sweep != 0.0 {
+            table.set("bit_crush", extended.bit_crush)?;
+            table.set("bit_crush_sweep", extended.bit_crush_sweep)?;
+        }
+        if extended.compression > 0.0 {
+            table.set("compression", extended.compression)?;
+        }
+        Ok(LuaValue::Table(table))
+    }
+}
@@ -0,0 +1,275 @@
+// Mechanical keyboard switch model: a noise transient for the contact, damped
+// keycap and plate modes for the body, and a faint ringing spring.
+
+use crate::mixer::SAMPLE_RATE;
+use crate::sound::get_freq;
+use crate::tuning::Tuning;
+use crate::voice::{clamp_seconds, normalize, Noise, Voice};
+use mlua::prelude::*;
+use std::f64::consts::TAU;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SwitchKind {
+    Linear,
+    /// A soft bump before bottoming out.
+    Tactile,
+    /// A click jacket snapping just before bottoming out.
+    Clicky,
+}
+
+impl SwitchKind {
+    pub fn parse(name: &str) -> Result<SwitchKind, String> {
+        match name {
+            "linear" => Ok(SwitchKind::Linear),
+            "tactile" => Ok(SwitchKind::Tactile),
+            "clicky" => Ok(SwitchKind::Clicky),
+            _ => Err(format!("unknown switch type '{}'", name)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SwitchKind::Linear => "linear",
+            SwitchKind::Tactile => "tactile",
+            SwitchKind::Clicky => "clicky",
+        }
+    }
+
+    fn body_freq(self) -> f64 {
+        match self {
+            SwitchKind::Linear => 900.0,
+            SwitchKind::Tactile => 1300.0,
+            SwitchKind::Clicky => 2000.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stroke {
+    /// Bottom-out as the key is pressed.
+    Press,
+    /// The stem returning to the top of the housing.
+    Release,
+    /// A press followed by its release.
+    Pair,
+}
+
+impl Stroke {
+    pub fn parse(name: &str) -> Result<Stroke, String> {
+        match name {
+            "press" => Ok(Stroke::Press),
+            "release" => Ok(Stroke::Release),
+            "pair" => Ok(Stroke::Pair),
+            _ => Err(format!("unknown stroke '{}'", name)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stroke::Press => "press",
+            Stroke::Release => "release",
+            Stroke::Pair => "pair",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwitchVoice {
+    pub kind: SwitchKind,
+    pub stroke: Stroke,
+    /// Main keycap and plate resonance in Hz.
+    pub body_freq: f64,
+    /// Seconds between press and release for `Stroke::Pair`.
+    pub hold: f64,
+    pub time_scale: f64,
+}
+
+// One impact: a noise burst exciting two body modes and the spring
+struct Hit {
+    at: f64,
+    amp: f32,
+    click: f64,
+    body_freq: f64,
+    body_decay: f64,
+    spring: f32,
+}
+
+const SPRING_FREQ: f64 = 4700.0;
+const SPRING_DECAY: f64 = 0.06;
+// Ratio of the second bending mode of a free plate to the first
+const PLATE_MODE: f64 = 2.76;
+
+impl SwitchVoice {
+    pub fn new(kind: SwitchKind, stroke: Stroke) -> Self {
+        Self {
+            kind,
+            stroke,
+            body_freq: kind.body_freq(),
+            hold: 0.09,
+            time_scale: 1.0,
+        }
+    }
+
+    fn press(&self, at: f64) -> Vec<Hit> {
+        let body = self.body_freq;
+        let bottom_out = |at, amp| Hit {
+            at,
+            amp,
+            click: 0.0006,
+            body_freq: body,
+            body_decay: 0.018,
+            spring: 0.05,
+        };
+        match self.kind {
+            SwitchKind::Linear => vec![bottom_out(at, 0.8)],
+            SwitchKind::Tactile => vec![
+                Hit {
+                    at,
+                    amp: 0.25,
+                    click: 0.0004,
+                    body_freq: body * 1.2,
+                    body_decay: 0.006,
+                    spring: 0.0,
+                },
+                bottom_out(at + 0.007, 0.7),
+            ],
+            SwitchKind::Clicky => vec![
+                Hit {
+                    at,
+                    amp: 1.0,
+                    click: 0.00025,
+                    body_freq: 4000.0,
+                    body_decay: 0.004,
+                    spring: 0.02,
+                },
+                Hit {
+                    at: at + 0.0025,
+                    amp: 0.5,
+                    click: 0.0002,
+                    body_freq: 4400.0,
+                    body_decay: 0.003,
+                    spring: 0.0,
+                },
+                bottom_out(at + 0.008, 0.6),
+            ],
+        }
+    }
+
+    fn release(&self, at: f64) -> Vec<Hit> {
+        let mut hits = vec![Hit {
+            at,
+            amp: 0.4,
+            click: 0.0005,
+            body_freq: self.body_freq * 1.35,
+            body_decay: 0.012,
+            spring: 0.08,
+        }];
+        if self.kind == SwitchKind::Clicky {
+            hits.push(Hit {
+                at: at + 0.002,
+                amp: 0.4,
+                click: 0.0002,
+                body_freq: 4000.0,
+                body_decay: 0.003,
+                spring: 0.0,
+            });
+        }
+        hits
+    }
+
+    fn hits(&self) -> Vec<Hit> {
+        match self.stroke {
+            Stroke::Press => self.press(0.0),
+            Stroke::Release => self.release(0.0),
+            Stroke::Pair => {
+                let mut hits = self.press(0.0);
+                hits.extend(self.release(self.hold));
+                hits
+            }
+        }
+    }
+
+    pub fn from_table(table: &LuaTable, tuning: &Tuning) -> LuaResult<SwitchVoice> {
+        let kind = match table.get::<Option<String>>("switch")? {
+            Some(name) => SwitchKind::parse(&name).map_err(mlua::Error::RuntimeError)?,
+            None => SwitchKind::Linear,
+        };
+        let stroke = match table.get::<Option<String>>("stroke")? {
+            Some(name) => Stroke::parse(&name).map_err(mlua::Error::RuntimeError)?,
+            None => Stroke::Press,
+        };
+
+        let mut voice = SwitchVoice::new(kind, stroke);
+        if let Some(freq) = get_freq(table, "base_freq", tuning)? {
+            voice.body_freq = freq.max(20.0);
+        }
+        if let Some(hold) = table.get::<Option<f64>>("hold")? {
+            voice.hold = clamp_seconds(hold);
+        }
+        Ok(voice)
+    }
+
+    /// Write the keys `from_table` reads into `table`.
+    pub fn to_table(&self, table: &LuaTable) -> LuaResult<()> {
+        table.set("switch", self.kind.name())?;
+        table.set("stroke", self.stroke.name())?;
+        table.set("base_freq", self.body_freq)?;
+        table.set("hold", self.hold)
+    }
+}
+
+impl Voice for SwitchVoice {
+    fn freq(&self) -> f64 {
+        self.body_freq
+    }
+
+    fn set_freq(&mut self, hz: f64) {
+        self.body_freq = hz;
+    }
+
+    fn set_note_length(&mut self, seconds: f32) {
+        self.hold = clamp_seconds(seconds as f64);
+    }
+
+    fn scale_time(&mut self, factor: f64) {
+        self.time_scale *= factor;
+    }
+
+    fn duration_samples(&self) -> usize {
+        let last = self.hits().iter().fold(0.0f64, |end, hit| end.max(hit.at));
+        ((last + SPRING_DECAY * 2.0) * self.time_scale * SAMPLE_RATE as f64).ceil() as usize
+    }
+
+    fn render(&self) -> Vec<f32> {
+        let rate = SAMPLE_RATE as f64;
+        let mut out = vec![0.0f32; self.duration_samples()];
+        let mut noise = Noise::new(self.body_freq.to_bits() as u32 ^ self.kind as u32);
+
+        for hit in self.hits() {
+            let start = (hit.at * self.time_scale * rate) as usize;
+            let click = hit.click * self.time_scale;
+            let body_decay = hit.body_decay * self.time_scale;
+            let spring_decay = SPRING_DECAY * self.time_scale;
+            let mut previous = 0.0;
+
+            for (i, slot) in out.iter_mut().skip(start).enumerate() {
+                let t = i as f64 / rate;
+                // High-passed noise makes the contact sharp rather than hissy
+                let white = noise.sample();
+                let transient = (white - previous) * (-t / click).exp() as f32;
+                previous = white;
+
+                let body = (TAU * hit.body_freq * t).sin() * (-t / body_decay).exp()
+                    + 0.5
+                        * (TAU * hit.body_freq * PLATE_MODE * t).sin()
+                        * (-t / (body_decay * 0.6)).exp();
+                let spring = (TAU * SPRING_FREQ * t).sin() * (-t / spring_decay).exp();
+
+                *slot += hit.amp * (transient + 0.6 * body as f32 + hit.spring * spring as f32);
+            }
+        }
+
+        normalize(&mut out, 1.0);
+        out
+    }
+}
@@ -0,0 +1,505 @@
+// Port of DrPetter's sfxr synthesis loop with bfxr's extensions: more waveforms,
+// overtones, bit crush and compression, plus user wavetables. Envelope, slides,
+// vibrato, arpeggio, repeat, filters and phaser follow the original.
+
+use crate::mixer::SAMPLE_RATE;
+use crate::voice::{Glide, Noise};
+use sfxr::{Sample, WaveType};
+use std::f32::consts::{PI, TAU};
+use std::sync::Arc;
+
+const PHASER_SIZE: usize = 1024;
+const OVERSAMPLING: usize = 8;
+const NOISE_SIZE: usize = 32;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Waveform {
+    Square,
+    Sawtooth,
+    Sine,
+    Noise,
+    Triangle,
+    PinkNoise,
+    Tan,
+    /// A sine with a faint sine 20 times higher.
+    Whistle,
+    /// A sharp, bright parabola.
+    Breaker,
+    /// Two-level noise from a 15-bit shift register.
+    OneBitNoise,
+    /// The shift register in its 93-step loop, a metallic tone.
+    Buzz,
+    /// Single-cycle table, normalized to -1.0..1.0.
+    Table(Arc<[f32]>),
+}
+
+impl Waveform {
+    /// sfxr wave types 0-4 followed by bfxr's 5-10.
+    pub fn from_index(index: i64) -> Result<Waveform, String> {
+        match index {
+            0 => Ok(Waveform::Square),
+            1 => Ok(Waveform::Sawtooth),
+            2 => Ok(Waveform::Sine),
+            3 => Ok(Waveform::Noise),
+            4 => Ok(Waveform::Triangle),
+            5 => Ok(Waveform::PinkNoise),
+            6 => Ok(Waveform::Tan),
+            7 => Ok(Waveform::Whistle),
+            8 => Ok(Waveform::Breaker),
+            9 => Ok(Waveform::OneBitNoise),
+            10 => Ok(Waveform::Buzz),
+            _ => Err(format!("Invalid wave_type: {} (expected 0-10)", index)),
+        }
+    }
+
+    /// The `wave_type` index, `None` for wavetables.
+    pub fn index(&self) -> Option<i64> {
+        match self {
+            Waveform::Square => Some(0),
+            Waveform::Sawtooth => Some(1),
+            Waveform::Sine => Some(2),
+            Waveform::Noise => Some(3),
+            Waveform::Triangle => Some(4),
+            Waveform::PinkNoise => Some(5),
+            Waveform::Tan => Some(6),
+            Waveform::Whistle => Some(7),
+            Waveform::Breaker => Some(8),
+            Waveform::OneBitNoise => Some(9),
+            Waveform::Buzz => Some(10),
+            Waveform::Table(_) => None,
+        }
+    }
+
+    /// The sfxr crate's equivalent, if it has one.
+    pub fn sfxr(&self) -> Option<WaveType> {
+        match self {
+            Waveform::Square => Some(WaveType::Square),
+            Waveform::Sawtooth => Some(WaveType::Sawtooth),
+            Waveform::Sine => Some(WaveType::Sine),
+            Waveform::Noise => Some(WaveType::Noise),
+            Waveform::Triangle => Some(WaveType::Triangle),
+            _ => None,
+        }
+    }
+
+    fn from_sfxr(wave_type: WaveType) -> Waveform {
+        match wave_type {
+            WaveType::Square => Waveform::Square,
+            WaveType::Sawtooth => Waveform::Sawtooth,
+            WaveType::Sine => Waveform::Sine,
+            WaveType::Noise => Waveform::Noise,
+            WaveType::Triangle => Waveform::Triangle,
+        }
+    }
+
+    fn is_noise(&self) -> bool {
+        matches!(
+            self,
+            Waveform::Noise | Waveform::PinkNoise | Waveform::OneBitNoise | Waveform::Buzz
+        )
+    }
+}
+
+/// bfxr parameters the sfxr crate has no room for. The default leaves a sound untouched.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Extended {
+    /// Replaces the sample's wave type when set.
+    pub waveform: Option<Waveform>,
+    /// Harmonics added on top of the waveform, 0-10.
+    pub overtones: u32,
+    /// Level lost by each successive overtone, 0.0-1.0.
+    pub overtone_falloff: f32,
+    /// Fraction of the sample rate thrown away by sample-and-hold, 0.0-1.0.
+    pub bit_crush: f32,
+    /// Change in bit crush per second.
+    pub bit_crush_sweep: f32,
+    /// Amount of upward compression, 0.0-1.0.
+    pub compression: f32,
+}
+
+impl Extended {
+    pub fn is_plain(&self) -> bool {
+        self.waveform.is_none()
+            && self.overtones == 0
+            && self.bit_crush == 0.0
+            && self.bit_crush_sweep == 0.0
+            && self.compression == 0.0
+    }
+}
+
+// Noise values refreshed once per oscillator period
+struct NoiseSource {
+    white: Noise,
+    pink: [f32; 7],
+    register: u16,
+    buffer: [f32; NOISE_SIZE],
+}
+
+impl NoiseSource {
+    fn new() -> Self {
+        Self {
+            white: Noise::new(0x5eed),
+            pink: [0.0; 7],
+            register: 0x7fff,
+            buffer: [0.0; NOISE_SIZE],
+        }
+    }
+
+    // Paul Kellet's refined pink noise filter
+    fn pink(&mut self) -> f32 {
+        let white = self.white.sample();
+        let b = &mut self.pink;
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.96900 * b[2] + white * 0.1538520;
+        b[3] = 0.86650 * b[3] + white * 0.3104856;
+        b[4] = 0.55000 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.0168980;
+        let out = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+        b[6] = white * 0.115926;
+        out * 0.11
+    }
+
+    fn bit(&mut self, tap: u16) -> f32 {
+        let feedback = (self.register ^ (self.register >> tap)) & 1;
+        self.register = (self.register >> 1) | (feedback << 14);
+        if self.register & 1 == 1 {
+            1.0
+        } else {
+            -1.0
+        }
+    }
+
+    fn refill(&mut self, waveform: &Waveform) {
+        let mut buffer = [0.0; NOISE_SIZE];
+        for slot in buffer.iter_mut() {
+            *slot = match waveform {
+                Waveform::PinkNoise => self.pink(),
+                Waveform::OneBitNoise => self.bit(1),
+                Waveform::Buzz => self.bit(6),
+                _ => self.white.sample(),
+            };
+        }
+        self.buffer = buffer;
+    }
+}
+
+struct Synth<'a> {
+    params: &'a Sample,
+    extended: &'a Extended,
+    waveform: &'a Waveform,
+    glide: &'a Glide,
+    noise: NoiseSource,
+    time: usize,
+    phase: usize,
+    period: f64,
+    max_period: f64,
+    slide: f64,
+    dslide: f64,
+    square_duty: f32,
+    square_slide: f32,
+    arp_mod: f64,
+    arp_time: usize,
+    arp_limit: usize,
+    rep_time: usize,
+    rep_limit: usize,
+    vib_phase: f64,
+    vib_speed: f64,
+    vib_amp: f64,
+    env_stage: usize,
+    env_time: usize,
+    env_length: [usize; 3],
+    lpf_pos: f32,
+    lpf_delta: f32,
+    lpf_cutoff: f32,
+    lpf_ramp: f32,
+    lpf_damping: f32,
+    hpf_pos: f32,
+    hpf_cutoff: f32,
+    hpf_ramp: f32,
+    phaser_offset: f32,
+    phaser_ramp: f32,
+    phaser_pos: usize,
+    phaser_buffer: [f32; PHASER_SIZE],
+    crush_rate: f32,
+    crush_phase: f32,
+    crush_last: f32,
+}
+
+impl<'a> Synth<'a> {
+    fn new(
+        params: &'a Sample,
+        extended: &'a Extended,
+        waveform: &'a Waveform,
+        glide: &'a Glide,
+    ) -> Self {
+        let lpf_cutoff = params.lpf_freq.powi(3) * 0.1;
+        let mut synth = Self {
+            params,
+            extended,
+            waveform,
+            glide,
+            noise: NoiseSource::new(),
+            time: 0,
+            phase: 0,
+            period: 0.0,
+            max_period: 0.0,
+            slide: 0.0,
+            dslide: 0.0,
+            square_duty: 0.0,
+            square_slide: 0.0,
+            arp_mod: 0.0,
+            arp_time: 0,
+            arp_limit: 0,
+            rep_time: 0,
+            rep_limit: if params.repeat_speed == 0.0 {
+                0
+            } else {
+                ((1.0 - params.repeat_speed).powi(2) * 20000.0 + 32.0) as usize
+            },
+            vib_phase: 0.0,
+            vib_speed: params.vib_speed.powi(2) * 0.01,
+            vib_amp: params.vib_strength * 0.5,
+            env_stage: 0,
+            env_time: 0,
+            env_length: [
+                (params.env_attack.powi(2) * 100000.0) as usize,
+                (params.env_sustain.powi(2) * 100000.0) as usize,
+                (params.env_decay.powi(2) * 100000.0) as usize,
+            ],
+            lpf_pos: 0.0,
+            lpf_delta: 0.0,
+            lpf_cutoff,
+            lpf_ramp: 1.0 + params.lpf_ramp * 0.0001,
+            lpf_damping: (5.0 / (1.0 + params.lpf_resonance.powi(2) * 20.0) * (0.01 + lpf_cutoff))
+                .min(0.8),
+            hpf_pos: 0.0,
+            hpf_cutoff: params.hpf_freq.powi(2) * 0.1,
+            hpf_ramp: 1.0 + params.hpf_ramp * 0.0003,
+            phaser_offset: params.pha_offset.powi(2) * 1020.0 * params.pha_offset.signum(),
+            phaser_ramp: params.pha_ramp.powi(2) * params.pha_ramp.signum(),
+            phaser_pos: 0,
+            phaser_buffer: [0.0; PHASER_SIZE],
+            crush_rate: 1.0 - extended.bit_crush.clamp(0.0, 1.0),
+            crush_phase: 0.0,
+            crush_last: 0.0,
+        };
+        synth.reset_pitch();
+        synth.noise.refill(waveform);
+        synth
+    }
+
+    // Called at the start and on every repeat
+    fn reset_pitch(&mut self) {
+        let params = self.params;
+        self.period = 100.0 / (params.base_freq.powi(2) + 0.001);
+        self.max_period = 100.0 / (params.freq_limit.powi(2) + 0.001);
+        self.slide = 1.0 - params.freq_ramp.powi(3) * 0.01;
+        self.dslide = -params.freq_dramp.powi(3) * 0.000001;
+        self.square_duty = 0.5 - params.duty * 0.5;
+        self.square_slide = -params.duty_ramp * 0.00005;
+        self.arp_mod = if params.arp_mod >= 0.0 {
+            1.0 - params.arp_mod.powi(2) * 0.9
+        } else {
+            1.0 + params.arp_mod.powi(2) * 10.0
+        };
+        self.arp_time = 0;
+        self.arp_limit = if params.arp_speed == 1.0 {
+            0
+        } else {
+            ((1.0 - params.arp_speed).powi(2) * 20000.0 + 32.0) as usize
+        };
+    }
+
+    // Envelope level for this sample, `None` once the decay has finished
+    fn envelope(&mut self) -> Option<f32> {
+        self.env_time += 1;
+        while self.env_time > self.env_length[self.env_stage] {
+            self.env_time = 0;
+            self.env_stage += 1;
+            if self.env_stage == 3 {
+                return None;
+            }
+        }
+        let progress = self.env_time as f32 / self.env_length[self.env_stage].max(1) as f32;
+        Some(match self.env_stage {
+            0 => progress,
+            1 => 1.0 + (1.0 - progress) * 2.0 * self.params.env_punch,
+            _ => 1.0 - progress,
+        })
+    }
+
+    // One cycle of the waveform at `phase` out of `period` oversampled steps
+    fn wave(&self, phase: usize, period: usize) -> f32 {
+        let position = phase as f32 / period as f32;
+        match self.waveform {
+            Waveform::Square => {
+                if position < self.square_duty {
+                    0.5
+                } else {
+                    -0.5
+                }
+            }
+            Waveform::Sawtooth => 1.0 - position * 2.0,
+            Waveform::Sine => (position * TAU).sin(),
+            Waveform::Triangle => 4.0 * (position - 0.5).abs() - 1.0,
+            Waveform::Tan => (PI * position).tan().clamp(-2.0, 2.0),
+            Waveform::Whistle => {
+                0.75 * (position * TAU).sin() + 0.25 * (position * 20.0 * TAU).sin()
+            }
+            Waveform::Breaker => {
+                let shifted = (position + 0.5).fract();
+                (1.0 - shifted * shifted * 2.0).abs() - 1.0
+            }
+            Waveform::Table(table) => table[phase * table.len() / period],
+            _ => self.noise.buffer[phase * NOISE_SIZE / period],
+        }
+    }
+
+    // The waveform with its overtones, each `overtone_falloff` quieter than the last
+    fn oscillator(&self, phase: usize, period: usize) -> f32 {
+        if self.extended.overtones == 0 {
+            return self.wave(phase, period);
+        }
+        let mut strength = 1.0;
+        let mut sample = 0.0;
+        for harmonic in 1..=self.extended.overtones as usize + 1 {
+            sample += strength * self.wave(phase * harmonic % period, period);
+            strength *= 1.0 - self.extended.overtone_falloff;
+        }
+        sample
+    }
+
+    fn step(&mut self) -> Option<f32> {
+        self.rep_time += 1;
+        if self.rep_limit != 0 && self.rep_time >= self.rep_limit {
+            self.rep_time = 0;
+            self.reset_pitch();
+        }
+
+        self.arp_time += 1;
+        if self.arp_limit != 0 && self.arp_time >= self.arp_limit {
+            self.arp_limit = 0;
+            self.period *= self.arp_mod;
+        }
+        self.slide += self.dslide;
+        self.period *= self.slide;
+        if self.period > self.max_period {
+            self.period = self.max_period;
+            if self.params.freq_limit > 0.0 {
+                return None;
+            }
+        }
+        let mut period = self.period;
+        if self.vib_amp > 0.0 {
+            self.vib_phase += self.vib_speed;
+            period *= 1.0 + self.vib_phase.sin() * self.vib_amp;
+        }
+        period /= self.glide.ratio(self.time);
+        self.time += 1;
+        let period = (period as usize).max(8);
+        self.square_duty = (self.square_duty + self.square_slide).clamp(0.0, 0.5);
+
+        let volume = self.envelope()?;
+
+        self.phaser_offset += self.phaser_ramp;
+        let phaser_delay = (self.phaser_offset.abs() as usize).min(PHASER_SIZE - 1);
+
+        self.hpf_cutoff = (self.hpf_cutoff * self.hpf_ramp).clamp(0.00001, 0.1);
+
+        let mut total = 0.0;
+        for _ in 0..OVERSAMPLING {
+            self.phase += 1;
+            if self.phase >= period {
+                self.phase %= period;
+                if self.waveform.is_noise() {
+                    self.noise.refill(self.waveform);
+                }
+            }
+            let mut sample = self.oscillator(self.phase, period);
+
+            let previous = self.lpf_pos;
+            self.lpf_cutoff = (self.lpf_cutoff * self.lpf_ramp).clamp(0.0, 0.1);
+            if self.params.lpf_freq != 1.0 {
+                self.lpf_delta += (sample - self.lpf_pos) * self.lpf_cutoff;
+                self.lpf_delta -= self.lpf_delta * self.lpf_damping;
+            } else {
+                self.lpf_pos = sample;
+                self.lpf_delta = 0.0;
+            }
+            self.lpf_pos += self.lpf_delta;
+
+            self.hpf_pos += self.lpf_pos - previous;
+            self.hpf_pos -= self.hpf_pos * self.hpf_cutoff;
+            sample = self.hpf_pos;
+
+            self.phaser_buffer[self.phaser_pos] = sample;
+            sample +=
+                self.phaser_buffer[(self.phaser_pos + PHASER_SIZE - phaser_delay) % PHASER_SIZE];
+            self.phaser_pos = (self.phaser_pos + 1) % PHASER_SIZE;
+
+            total += sample * volume;
+        }
+        let mut sample = total / OVERSAMPLING as f32;
+
+        // Sample-and-hold at a fraction of the output rate
+        self.crush_phase += self.crush_rate;
+        if self.crush_phase >= 1.0 {
+            self.crush_phase -= 1.0;
+            self.crush_last = sample;
+        }
+        self.crush_rate =
+            (self.crush_rate - self.extended.bit_crush_sweep / SAMPLE_RATE as f32).clamp(0.0, 1.0);
+        sample = self.crush_last;
+
+        if self.extended.compression > 0.0 {
+            let exponent = 1.0 / (1.0 + 4.0 * self.extended.compression);
+            sample = sample.abs().powf(exponent).copysign(sample);
+        }
+        Some(sample)
+    }
+}
+
+/// Render `length` samples of `params` with the bfxr extensions and `glide` applied.
+/// Wavetables and noise are stepped through without interpolation, like the Game Boy
+/// wave channel.
+pub fn render(
+    params: &Sample,
+    extended: &Extended,
+    glide: &Glide,
+    volume: f32,
+    length: usize,
+) -> Vec<f32> {
+    let waveform = match &extended.waveform {
+        Some(waveform) => waveform.clone(),
+        None => Waveform::from_sfxr(params.wave_type),
+    };
+    let mut synth = Synth::new(params, extended, &waveform, glide);
+    let mut buffer = vec![0.0; length];
+    for slot in buffer.iter_mut() {
+        match synth.step() {
+            Some(sample) => *slot = (sample * 2.0 * volume).clamp(-1.0, 1.0),
+            None => break,
+        }
+    }
+    buffer
+}
+
+/// Rescale table values to fill -1.0..1.0, so 4-bit 0-15 tables can be used as is.
+pub fn normalize_wavetable(values: &[f32]) -> Vec<f32> {
+    let (min, max) = values.iter().fold((f32::MAX, f32::MIN), |(min, max), v| {
+        (min.min(*v), max.max(*v))
+    });
+    let middle = (max + min) / 2.0;
+    let half_range = (max - min) / 2.0;
+    values
+        .iter()
+        .map(|v| {
+            if half_range > 0.0 {
+                (v - middle) / half_range
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
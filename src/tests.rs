@@ -293,3 +293,1269 @@ fn test_append_sound() {
     // Stop playback.
     assert!(player.stop().is_ok());
 }
+
+#[test]
+fn test_transform_transpose_and_time_scale() {
+    use crate::sound::Transform;
+
+    let mut sample = Sample::new();
+    sample.base_freq = 0.3;
+    sample.env_decay = 0.4;
+    let params = SoundParams::new(sample);
+
+    let identity = params.transformed(&Transform::default());
+    assert_eq!(identity.duration_samples(), params.duration_samples());
+
+    // One octave up doubles the frequency in Hz
+    let octave = params.transformed(&Transform {
+        transpose: 12.0,
+        time_scale: 1.0,
+    });
+    let hz = |p: &SoundParams| {
+        let b = p.generator().sample.base_freq;
+        (b * b + 0.001) * 8.0 * 44100.0 / 100.0
+    };
+    assert!((hz(&octave) / hz(&params) - 2.0).abs() < 1e-6);
+
+    let snappy = params.transformed(&Transform {
+        transpose: 0.0,
+        time_scale: 0.5,
+    });
+    let ratio = snappy.duration_samples() as f64 / params.duration_samples() as f64;
+    assert!((ratio - 0.5).abs() < 0.01);
+
+    // Per-sample ramps sweep as far over the stretched sound as over the original
+    let mut sample = Sample::new();
+    sample.freq_dramp = 0.5;
+    sample.duty_ramp = 0.4;
+    sample.pha_ramp = 0.3;
+    sample.lpf_ramp = -0.6;
+    sample.hpf_ramp = 0.2;
+    let ramps = SoundParams::new(sample);
+    let slow = ramps.transformed(&Transform {
+        transpose: 0.0,
+        time_scale: 2.0,
+    });
+    let (from, to) = (ramps.generator().sample, slow.generator().sample);
+    // sfxr adds freq_dramp^3 to a per-sample slide, so it scales with 1/factor^2
+    assert!((to.freq_dramp.powi(3) * 4.0 - from.freq_dramp.powi(3)).abs() < 1e-9);
+    assert!((to.duty_ramp * 2.0 - from.duty_ramp).abs() < 1e-6);
+    assert!((to.pha_ramp.powi(2) * 2.0 - from.pha_ramp.powi(2)).abs() < 1e-6);
+    let per_sample = |v: f32, step: f64| 1.0 + v as f64 * step;
+    assert!(
+        (per_sample(to.lpf_ramp, 0.0001).powi(2) - per_sample(from.lpf_ramp, 0.0001)).abs() < 1e-9
+    );
+    assert!(
+        (per_sample(to.hpf_ramp, 0.0003).powi(2) - per_sample(from.hpf_ramp, 0.0003)).abs() < 1e-9
+    );
+}
+
+#[test]
+fn test_player_transform_settings() {
+    let player = Player::new().unwrap();
+
+    assert!(player.set_transpose(12.0, 0.0).is_ok());
+    assert!(player.set_time_scale(0.5).is_ok());
+    assert!(player.set_time_scale(0.0).is_err());
+    assert!(player.set_time_scale(f64::NAN).is_err());
+
+    let transform = player.transform().unwrap();
+    assert_eq!(transform.transpose, 12.0);
+    assert_eq!(transform.time_scale, 0.5);
+
+    assert!(player.play(SoundParams::new(Sample::blip(None))).is_ok());
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(player.stop().is_ok());
+}
+
+#[test]
+fn test_mixer_schedules_sample_accurately() {
+    use crate::mixer::{Mixer, SAMPLE_RATE};
+
+    let mixer = Mixer::new();
+    let mut source = mixer.source();
+    assert!(mixer
+        .schedule_after(300, Box::new(vec![0.5; 4].into_iter()))
+        .is_ok());
+    assert!(mixer
+        .schedule_at(302, Box::new(vec![0.25; 2].into_iter()))
+        .is_ok());
+
+    let output: Vec<f32> = (&mut source).take(310).collect();
+    assert!(output[..300].iter().all(|s| *s == 0.0));
+    assert_eq!(&output[300..306], &[0.5, 0.5, 0.75, 0.75, 0.0, 0.0]);
+    assert_eq!(mixer.clock().unwrap(), 512);
+    assert_eq!(crate::mixer::ms_to_samples(1000.0), SAMPLE_RATE as u64);
+}
+
+#[test]
+fn test_delayed_playback() {
+    let player = Player::new().unwrap();
+    let start = player.clock_ms().unwrap();
+
+    assert!(player
+        .play_after(SoundParams::new(Sample::blip(None)), 50.0)
+        .is_ok());
+    assert!(player
+        .play_at_time(SoundParams::new(Sample::pickup(None)), start + 100.0)
+        .is_ok());
+    // Times in the past start immediately
+    assert!(player
+        .play_at_time(SoundParams::new(Sample::hit(None)), 0.0)
+        .is_ok());
+
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(player.clock_ms().unwrap() > start);
+    assert!(player.stop().is_ok());
+}
+
+#[test]
+fn test_timeline_render() {
+    use crate::sound::Transform;
+    use crate::timeline::{mix_into, Timeline, TimelineEntry};
+
+    let note = SoundParams::new(Sample::blip(None));
+    let len = note.duration_samples();
+
+    // Entries without an offset follow the previous entry
+    let mut sequence = Timeline::new();
+    sequence.push(TimelineEntry::new(note.clone()));
+    sequence.push(TimelineEntry::new(note.clone()));
+    assert_eq!(
+        sequence.render(&Transform::default()).unwrap().len(),
+        len * 2
+    );
+
+    // Entries at the same offset overlap into a chord
+    let mut chord = Timeline::new();
+    chord.push(TimelineEntry::new(note.clone()).at(0.0));
+    chord.push(TimelineEntry::new(note.clone()).at(0.0).with_gain(0.5));
+    chord.push(TimelineEntry::new(note.clone()).at(10.0).with_gain(0.0));
+    let rendered = chord.render(&Transform::default()).unwrap();
+    assert_eq!(rendered.len(), len + 441);
+    assert!(rendered.iter().all(|s| (-1.0..=1.0).contains(s)));
+
+    // Offsets past the length cap fail instead of allocating
+    for at_ms in [1e12, f64::INFINITY] {
+        let mut far = Timeline::new();
+        far.push(TimelineEntry::new(note.clone()).at(at_ms));
+        assert!(far.render(&Transform::default()).is_err());
+    }
+    let mut out = Vec::new();
+    assert!(mix_into(&mut out, &[0.5; 4], usize::MAX, 1.0).is_err());
+    assert!(out.is_empty());
+
+    let player = Player::new().unwrap();
+    assert!(player.play_timeline(&chord).is_ok());
+    assert!(player.append_timeline(&sequence).is_ok());
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(player.stop().is_ok());
+}
+
+#[test]
+fn test_note_names() {
+    use crate::pitch::{freq_to_note, note_to_freq, parse_note};
+    use crate::tuning::Tuning;
+
+    let equal = Tuning::default();
+
+    assert!((note_to_freq("A4", &equal).unwrap() - 440.0).abs() < 1e-9);
+    assert!((note_to_freq("C#4", &equal).unwrap() - 277.18).abs() < 0.01);
+    assert!((note_to_freq("Db4", &equal).unwrap() - 277.18).abs() < 0.01);
+    assert!((note_to_freq("C-1", &equal).unwrap() - 8.1758).abs() < 0.001);
+    assert_eq!(parse_note("60").unwrap(), 60.0);
+    assert!((parse_note("A4+15c").unwrap() - 69.15).abs() < 1e-9);
+    assert!((parse_note("A4-50c").unwrap() - 68.5).abs() < 1e-9);
+    assert_eq!(parse_note("69+50c").unwrap(), 69.5);
+
+    assert!(parse_note("").is_err());
+    assert!(parse_note("H4").is_err());
+    assert!(parse_note("C").is_err());
+    assert!(parse_note("A4+15").is_err());
+
+    let (name, cents) = freq_to_note(554.37, &equal).unwrap();
+    assert_eq!(name, "C#5");
+    assert!(cents.abs() < 1.0);
+    let (name, cents) = freq_to_note(446.0, &equal).unwrap();
+    assert_eq!(name, "A4");
+    assert!((cents - 23.45).abs() < 0.1);
+    assert!(freq_to_note(0.0, &equal).is_err());
+}
+
+#[test]
+fn test_mml_parse() {
+    use crate::mml::parse;
+
+    let voices = parse("t120 o4 l8 cdefgab>c").unwrap();
+    assert_eq!(voices.len(), 1);
+    let notes = &voices[0];
+    assert_eq!(notes.len(), 8);
+    assert_eq!(notes[0].midi, 60.0);
+    assert_eq!(notes[7].midi, 72.0);
+    assert_eq!(notes[1].start_ms, 250.0);
+    assert_eq!(notes[0].length_ms, 250.0);
+
+    // Accidentals, dots, rests, ties, volume and voices
+    let voices = parse("c+4. r8 v5 e-4&e-4 d4^8, o3 l2 c < c").unwrap();
+    assert_eq!(voices.len(), 2);
+    assert_eq!(voices[0][0].midi, 61.0);
+    assert_eq!(voices[0][0].length_ms, 750.0);
+    assert_eq!(voices[0][1].midi, 63.0);
+    assert_eq!(voices[0][1].start_ms, 1000.0);
+    assert_eq!(voices[0][1].length_ms, 1000.0);
+    assert_eq!(voices[0][1].gain, 5.0 / 15.0);
+    assert_eq!(voices[0][2].length_ms, 750.0);
+    assert_eq!(voices[1][1].midi, 36.0);
+    assert_eq!(voices[1][1].start_ms, 1000.0);
+
+    let err = parse("c4 x").unwrap_err();
+    assert_eq!(err.position, 3);
+    assert!(parse("l0 c").is_err());
+    assert!(parse("t0 c").is_err());
+    assert!(parse("o c").is_err());
+
+    // Octaves outside 0-10 are rejected rather than overflowing
+    assert_eq!(parse("o10 b").unwrap()[0][0].midi, 131.0);
+    assert_eq!(parse("o200000000 c").unwrap_err().position, 0);
+    assert!(parse("o3000000000 c").is_err());
+    assert_eq!(parse("o10 >c").unwrap_err().position, 4);
+    assert!(parse("o0 <c").is_err());
+}
+
+#[test]
+fn test_mml_playback() {
+    let player = Player::new().unwrap();
+    let instrument = SoundParams::new(Sample::blip(None));
+    let timeline = crate::mml::timeline(
+        "t240 l16 o5 ceg>c, o4 l4 c",
+        &[instrument],
+        &Default::default(),
+    )
+    .unwrap();
+
+    assert!(player.play_timeline(&timeline).is_ok());
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(player.stop().is_ok());
+}
+
+#[test]
+fn test_loop_source() {
+    use crate::mixer::LoopSource;
+
+    // Tails longer than the period overlap into the next iteration
+    let looped: Vec<f32> = LoopSource::new(vec![1.0, 0.5, 0.25], 2, Some(3)).collect();
+    assert_eq!(looped, vec![1.0, 0.5, 1.25, 0.5, 1.25, 0.5, 0.25]);
+
+    let endless = LoopSource::new(vec![1.0], 4, None);
+    assert_eq!(endless.take(1000).filter(|s| *s == 1.0).count(), 250);
+}
+
+#[test]
+fn test_sequencer_patterns() {
+    use crate::sequencer::{parse_pattern, Sequence, Step, Track};
+
+    assert_eq!(
+        parse_pattern("x.-x").unwrap(),
+        vec![Step::Hit, Step::Rest, Step::Rest, Step::Hit]
+    );
+    assert_eq!(
+        parse_pattern("C4 . A4 x").unwrap(),
+        vec![Step::Note(60.0), Step::Rest, Step::Note(69.0), Step::Hit]
+    );
+    assert!(parse_pattern("C4 . Q4").is_err());
+
+    let mut lead = Track::new(
+        SoundParams::new(Sample::blip(None)),
+        parse_pattern("C5 E5 G5 C6").unwrap(),
+    );
+    lead.bpm = 240.0;
+    assert_eq!(lead.step_ms(), 62.5);
+    assert_eq!(lead.period_ms(), 250.0);
+
+    let mut drums = Track::new(
+        SoundParams::new(Sample::hit(None)),
+        parse_pattern("x.x.").unwrap(),
+    );
+    drums.swing = 1.0;
+    drums.loops = None;
+
+    let player = Player::new().unwrap();
+    let sequence = Sequence {
+        tracks: vec![lead, drums],
+    };
+    let id = player.play_sequence(&sequence).unwrap();
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(player.is_sequence_playing(id).unwrap());
+    assert!(player.stop_sequence(id).unwrap());
+    assert!(!player.is_sequence_playing(id).unwrap());
+    assert!(player.stop().is_ok());
+}
+
+#[test]
+fn test_midi_parse() {
+    use crate::midi::{parse, timeline, MidiOptions};
+
+    #[rustfmt::skip]
+    let data: Vec<u8> = vec![
+        // Header: format 1, two tracks, 480 ticks per quarter
+        b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 1, 0, 2, 0x01, 0xE0,
+        // Tempo track: 120 bpm, then 60 bpm after one quarter
+        b'M', b'T', b'r', b'k', 0, 0, 0, 19,
+        0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20,
+        0x83, 0x60, 0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40,
+        0x00, 0xFF, 0x2F, 0x00,
+        // Note track using running status and velocity 0 note offs
+        b'M', b'T', b'r', b'k', 0, 0, 0, 20,
+        0x00, 0x90, 60, 100,
+        0x83, 0x60, 60, 0,
+        0x00, 64, 127,
+        0x83, 0x60, 0x80, 64, 0,
+        0x00, 0xFF, 0x2F, 0x00,
+    ];
+
+    let notes = parse(&data).unwrap();
+    assert_eq!(notes.len(), 2);
+    assert_eq!(notes[0].key, 60);
+    assert_eq!(notes[0].start_ms, 0.0);
+    assert_eq!(notes[0].length_ms, 500.0);
+    assert_eq!(notes[1].key, 64);
+    assert_eq!(notes[1].velocity, 127);
+    assert_eq!(notes[1].start_ms, 500.0);
+    assert_eq!(notes[1].length_ms, 1000.0);
+
+    let rendered = timeline(&notes, &MidiOptions::default(), &Default::default())
+        .render(&Default::default())
+        .unwrap();
+    assert!(!rendered.is_empty());
+
+    assert!(parse(b"RIFF").is_err());
+    assert!(parse(&data[..30]).is_err());
+
+    // A meta event cancels running status, so a data byte after it is an error
+    #[rustfmt::skip]
+    let after_meta: Vec<u8> = vec![
+        b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0x01, 0xE0,
+        b'M', b'T', b'r', b'k', 0, 0, 0, 15,
+        0x00, 0x90, 60, 100,
+        0x00, 0xFF, 0x01, 0x00,
+        0x00, 60, 0,
+        0x00, 0xFF, 0x2F, 0x00,
+    ];
+    assert!(parse(&after_meta).is_err());
+}
+
+#[test]
+fn test_tracker_module() {
+    use crate::tracker::{ModPlayer, Module};
+    use std::sync::Arc;
+
+    let mut data = vec![0u8; 1084];
+    // Sample 1: 32 bytes, full volume, looped over its whole length
+    data[42..44].copy_from_slice(&16u16.to_be_bytes());
+    data[45] = 64;
+    data[48..50].copy_from_slice(&16u16.to_be_bytes());
+    data[950] = 1;
+    data[1080..1084].copy_from_slice(b"M.K.");
+
+    // One pattern: C-2 with sample 1 on row 0, pattern break on row 1
+    let mut pattern = vec![0u8; 64 * 4 * 4];
+    pattern[..4].copy_from_slice(&[0x01, 0xAC, 0x10, 0x00]);
+    pattern[16..20].copy_from_slice(&[0x00, 0x00, 0x0D, 0x00]);
+    data.extend(pattern);
+    data.extend((0..32).map(|i| if i % 2 == 0 { 64u8 } else { (-64i8) as u8 }));
+
+    let module = Arc::new(Module::parse(&data).unwrap());
+
+    // Two rows of six ticks at 125 bpm, then the song ends
+    let samples: Vec<f32> = ModPlayer::new(module.clone(), false, 1.0).collect();
+    assert_eq!(samples.len(), 2 * 6 * 882);
+    assert!(samples.iter().any(|s| s.abs() > 0.0));
+
+    let looped = ModPlayer::new(module, true, 1.0).take(50_000).count();
+    assert_eq!(looped, 50_000);
+
+    data[1080..1084].copy_from_slice(b"8CHN");
+    assert!(Module::parse(&data).is_err());
+    assert!(Module::parse(&data[..100]).is_err());
+}
+
+#[test]
+fn test_chords() {
+    use crate::chord::{
+        arpeggiate, intervals, timeline, ArpPattern, Arpeggio, ChordOptions, MAX_OCTAVES,
+    };
+
+    assert_eq!(intervals("major").unwrap(), &[0, 4, 7]);
+    assert_eq!(intervals("m7").unwrap(), &[0, 3, 7, 10]);
+    assert!(intervals("nope").is_err());
+
+    let notes = [60.0, 64.0, 67.0];
+    assert_eq!(
+        arpeggiate(&notes, ArpPattern::Up, 2),
+        vec![60.0, 64.0, 67.0, 72.0, 76.0, 79.0]
+    );
+    assert_eq!(
+        arpeggiate(&notes, ArpPattern::Down, 1),
+        vec![67.0, 64.0, 60.0]
+    );
+    assert_eq!(
+        arpeggiate(&notes, ArpPattern::UpDown, 1),
+        vec![60.0, 64.0, 67.0, 64.0, 60.0]
+    );
+    assert_eq!(
+        arpeggiate(&notes, ArpPattern::Up, u32::MAX).len(),
+        notes.len() * MAX_OCTAVES as usize
+    );
+    let mut random = arpeggiate(&notes, ArpPattern::Random, 1);
+    random.sort_by(f64::total_cmp);
+    assert_eq!(random, notes);
+
+    let instrument = SoundParams::new(Sample::new());
+    let chord = timeline(
+        60.0,
+        "major",
+        &instrument,
+        &ChordOptions::default(),
+        &Default::default(),
+    )
+    .unwrap();
+    let arpeggio = timeline(
+        60.0,
+        "major",
+        &instrument,
+        &ChordOptions {
+            arpeggio: Some(Arpeggio {
+                pattern: ArpPattern::Up,
+                rate_ms: 100.0,
+                octaves: 1,
+            }),
+            ..Default::default()
+        },
+        &Default::default(),
+    )
+    .unwrap();
+    let chord = chord.render(&Default::default()).unwrap();
+    let arpeggio = arpeggio.render(&Default::default()).unwrap();
+    assert!(!chord.is_empty());
+    assert!(arpeggio.len() > crate::mixer::ms_to_samples(200.0) as usize);
+
+    let player = Player::new().unwrap();
+    let options = ChordOptions::default();
+    let timeline = timeline(57.0, "m7", &instrument, &options, &Default::default()).unwrap();
+    assert!(player.start_timeline(&timeline, options.mode).is_ok());
+    assert!(player.stop().is_ok());
+}
+
+#[test]
+fn test_glide() {
+    use crate::timeline::{Timeline, TimelineEntry};
+
+    let crossings = |buffer: &[f32]| {
+        buffer
+            .windows(2)
+            .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+            .count()
+    };
+    let note = SoundParams::new(Sample::new()).with_freq(440.0);
+    let plain = note.render();
+
+    // Gliding from the same pitch is a plain render of the gliding sound
+    let glided = note.clone().with_glide(100.0);
+    assert_eq!(glided.glide_ms(), 100.0);
+    assert_eq!(glided.render_glide(440.0), glided.render());
+    assert_eq!(glided.render().len(), plain.len());
+
+    // The oscillator bends while the envelope and length stay put
+    let from_above = glided.render_glide(880.0);
+    let from_below = glided.render_glide(220.0);
+    assert_eq!(from_above.len(), plain.len());
+    assert_eq!(from_below.len(), plain.len());
+    assert!(crossings(&from_above[..2000]) > crossings(&from_below[..2000]));
+
+    let mut sequence = Timeline::new();
+    sequence.push(TimelineEntry::new(note.clone().with_freq(880.0)));
+    sequence.push(TimelineEntry::new(glided.clone()));
+    let rendered = sequence.render(&Default::default()).unwrap();
+    assert_eq!(rendered.len(), plain.len() * 2);
+    assert_ne!(&rendered[plain.len()..], &glided.render()[..]);
+
+    // Notes starting together don't glide into each other
+    let chord = |first: SoundParams, second: SoundParams| {
+        let mut chord = Timeline::new();
+        chord.push(TimelineEntry::new(first).at(0.0));
+        chord.push(TimelineEntry::new(second).at(0.0));
+        chord.render(&Default::default()).unwrap()
+    };
+    let high = note.clone().with_freq(880.0);
+    assert_eq!(
+        chord(high.clone(), glided.clone()),
+        chord(glided.clone(), high)
+    );
+
+    let player = Player::new().unwrap();
+    assert!(player.append(note.with_freq(880.0)).is_ok());
+    assert!(player.append(glided).is_ok());
+    assert!(player.stop().is_ok());
+}
+
+#[test]
+fn test_scala_tuning() {
+    use crate::tuning::{KeyboardMapping, Scale, Tuning};
+
+    let scl =
+        "! just.scl\n!\n5-limit just major\n 7\n!\n 9/8\n 5/4\n 4/3\n 3/2\n 5/3\n 15/8\n 2/1\n";
+    let scale = Scale::parse(scl).unwrap();
+    assert_eq!(scale.cents.len(), 7);
+    assert!((scale.cents[3] - 701.955).abs() < 0.001);
+    assert!((scale.cents_at(7) - 1200.0).abs() < 1e-9);
+    assert!((scale.cents_at(-1) - (1200.0 - 111.731)).abs() < 0.001);
+
+    // White keys only, C4 as the tonic and A4 at 440 Hz
+    let kbm = "! white.kbm\n12\n0\n127\n60\n69\n440.0\n7\n0\nx\n1\nx\n2\n3\nx\n4\nx\n5\nx\n6\n";
+    let mapping = KeyboardMapping::parse(kbm).unwrap();
+    assert_eq!(mapping.degree(62), 1);
+    assert_eq!(mapping.degree(61), 0);
+    assert_eq!(mapping.degree(72), 7);
+
+    let tuning = Tuning::from_scala(scale, Some(mapping));
+    assert!((tuning.midi_to_freq(69.0) - 440.0).abs() < 1e-9);
+    // A4 is the major sixth 5/3 above C4
+    assert!((tuning.midi_to_freq(60.0) - 264.0).abs() < 1e-9);
+    assert!((tuning.midi_to_freq(67.0) - 396.0).abs() < 1e-9);
+    assert!((tuning.midi_to_freq(81.0) - 880.0).abs() < 1e-9);
+
+    let equal = Tuning {
+        reference_freq: 432.0,
+        ..Tuning::default()
+    };
+    assert!((equal.midi_to_freq(69.0) - 432.0).abs() < 1e-9);
+    assert!((equal.midi_to_freq(57.0) - 216.0).abs() < 1e-9);
+    assert!((equal.freq_to_midi(864.0) - 81.0).abs() < 1e-9);
+
+    // Each player keeps its own tuning
+    let player = Player::new().unwrap();
+    assert!(player.set_reference_freq(432.0).is_ok());
+    assert_eq!(player.tuning().unwrap(), equal);
+    assert!(player.set_reference_freq(0.0).is_err());
+    assert_eq!(Player::new().unwrap().tuning().unwrap(), Tuning::default());
+    assert!(player.set_tuning(tuning.clone()).is_ok());
+    assert_eq!(player.tuning().unwrap(), tuning);
+
+    assert!(Scale::parse("bad\nx\n").is_err());
+    assert!(Scale::parse("short\n3\n9/8\n").is_err());
+
+    // Map sizes past the MIDI range and missing mapping lines are errors
+    let header = "0\n127\n60\n69\n440.0\n12\n";
+    assert!(KeyboardMapping::parse(&format!("100000000\n{}", header)).is_err());
+    assert!(KeyboardMapping::parse(&format!("-1\n{}", header)).is_err());
+    assert!(KeyboardMapping::parse(&format!("3\n{}0\n1\n", header)).is_err());
+    assert!(KeyboardMapping::parse(&format!("0\n{}", header)).is_ok());
+}
+
+#[test]
+fn test_musical_typing() {
+    use crate::musical::{mode_intervals, parse_degree, MusicalTyping};
+
+    assert_eq!(parse_degree("IV").unwrap(), 4);
+    assert_eq!(parse_degree("vi").unwrap(), 6);
+    assert_eq!(parse_degree("5").unwrap(), 5);
+    assert!(parse_degree("VIII").is_err());
+    assert!(mode_intervals("nope").is_err());
+
+    // C major, I then V, two notes per chord
+    let mut typing = MusicalTyping::new(60.0, mode_intervals("major").unwrap(), vec![1, 5]);
+    typing.notes_per_chord = 2;
+    let notes: Vec<f64> = (0..6).map(|_| typing.next_note()).collect();
+    assert_eq!(notes, vec![60.0, 64.0, 67.0, 71.0, 60.0, 64.0]);
+
+    let player = Player::new().unwrap();
+    let typing = MusicalTyping::new(72.0, mode_intervals("minor").unwrap(), vec![1, 4]);
+    assert!(player.set_musical_typing(Some(typing)).is_ok());
+    assert!(player.play(SoundParams::new(Sample::new())).is_ok());
+    assert!(player.set_musical_typing(None).is_ok());
+    assert!(player.stop().is_ok());
+}
+
+#[test]
+fn test_key_variation() {
+    use crate::keys::{variation, vary, KeyClass};
+    use crate::musical::{mode_intervals, MusicalTyping};
+
+    assert_eq!(KeyClass::of("a"), KeyClass::Letter);
+    assert_eq!(KeyClass::of("É"), KeyClass::Letter);
+    assert_eq!(KeyClass::of("7"), KeyClass::Digit);
+    assert_eq!(KeyClass::of(";"), KeyClass::Punctuation);
+    assert_eq!(KeyClass::of(" "), KeyClass::Space);
+    assert_eq!(KeyClass::of("<CR>"), KeyClass::Enter);
+    assert_eq!(KeyClass::of("<BS>"), KeyClass::Backspace);
+    assert_eq!(KeyClass::of("<Tab>"), KeyClass::Other);
+
+    // Deterministic per character, different between characters
+    assert_eq!(variation("a"), variation("a"));
+    assert_ne!(variation("a").0, variation("s").0);
+    let (letter, _) = variation("a");
+    assert!(letter.transpose.abs() <= 0.4);
+    let (enter, gain) = variation("<CR>");
+    assert_eq!(enter.transpose, -12.0);
+    assert!(enter.time_scale > 1.0 && gain > 1.0);
+
+    let params = SoundParams::new(Sample::new()).with_freq(440.0);
+    let space = vary(" ", &params);
+    assert!(space.freq() < params.freq());
+    assert!(space.duration_samples() > params.duration_samples());
+
+    let player = Player::new().unwrap();
+    assert!(player.play_key("x", params.clone()).is_ok());
+
+    // Musical typing picks the note, the key detunes it
+    let typing = MusicalTyping::new(60.0, mode_intervals("major").unwrap(), vec![1]);
+    assert!(player.set_musical_typing(Some(typing)).is_ok());
+    let keyed = player.key_sound("a", params.clone()).unwrap();
+    let note = player.tuning().unwrap().midi_to_freq(60.0);
+    let expected = note * 2f64.powf(letter.transpose / 12.0);
+    assert!((keyed.freq() / expected - 1.0).abs() < 1e-3);
+    assert!(player.play_key("a", params).is_ok());
+    assert!(player.set_musical_typing(None).is_ok());
+    assert!(player.stop().is_ok());
+}
+
+#[test]
+fn test_switch_voice() {
+    use crate::sound::Transform;
+    use crate::switch::{Stroke, SwitchKind, SwitchVoice};
+    use crate::voice::{Engine, Voice, MAX_SECONDS};
+
+    for kind in [SwitchKind::Linear, SwitchKind::Tactile, SwitchKind::Clicky] {
+        let voice = SwitchVoice::new(kind, Stroke::Press);
+        let buffer = voice.render();
+        assert_eq!(buffer.len(), voice.duration_samples());
+        let peak = buffer.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        assert!((peak - 1.0).abs() < 1e-6);
+    }
+
+    // The pair holds for the note length before releasing
+    let mut pair = SwitchVoice::new(SwitchKind::Clicky, Stroke::Pair);
+    let press = SwitchVoice::new(SwitchKind::Clicky, Stroke::Press);
+    assert!(pair.duration_samples() > press.duration_samples());
+    pair.set_note_length(0.2);
+    assert!(pair.duration_samples() > crate::mixer::ms_to_samples(200.0) as usize);
+    let mut long = pair.clone();
+    long.set_note_length(1e7);
+    assert!(
+        long.duration_samples() < crate::mixer::ms_to_samples(2.0 * MAX_SECONDS * 1000.0) as usize
+    );
+
+    let params = SoundParams::from_engine(Engine::Switch(press)).with_volume(0.5);
+    assert_eq!(params.freq(), 2000.0);
+    let higher = params.transformed(&Transform {
+        transpose: 12.0,
+        time_scale: 2.0,
+    });
+    assert!((higher.freq() - 4000.0).abs() < 1e-9);
+    assert!(higher.duration_samples() > params.duration_samples());
+    let peak = params
+        .render()
+        .iter()
+        .fold(0.0f32, |max, s| max.max(s.abs()));
+    assert!((peak - 0.5).abs() < 1e-6);
+
+    let player = Player::new().unwrap();
+    assert!(player.play(params).is_ok());
+    assert!(player.stop().is_ok());
+}
+
+#[test]
+fn test_fm_voice() {
+    use crate::fm::{Algorithm, FmVoice};
+    use crate::mixer::SAMPLE_RATE;
+    use crate::sound::Transform;
+    use crate::voice::{Engine, Voice, MAX_SECONDS};
+
+    let mut voice = FmVoice::new(220.0);
+    // Hold plus the carrier's release
+    assert_eq!(voice.duration_samples(), 13230);
+    let stack = voice.render();
+    assert_eq!(stack.len(), voice.duration_samples());
+    assert!(stack.iter().all(|s| s.abs() <= 1.0));
+    assert!(stack.iter().any(|s| s.abs() > 0.5));
+    // Faded out by the end of the release
+    assert!(stack.last().unwrap().abs() < 0.01);
+
+    voice.algorithm = Algorithm::Parallel;
+    assert_ne!(voice.render(), stack);
+    assert!(Algorithm::parse("ring").is_err());
+
+    voice.set_note_length(0.5);
+    assert_eq!(voice.duration_samples(), 26460);
+
+    // Hold and release are capped however far the note is stretched
+    let mut long = voice.clone();
+    long.scale_time(1e9);
+    assert_eq!(
+        long.duration_samples(),
+        (2.0 * MAX_SECONDS * SAMPLE_RATE as f64) as usize
+    );
+
+    let params = SoundParams::from_engine(Engine::Fm(voice));
+    let higher = params.transformed(&Transform {
+        transpose: 12.0,
+        time_scale: 0.5,
+    });
+    assert!((higher.freq() - 440.0).abs() < 1e-9);
+    assert_eq!(higher.duration_samples(), 13230);
+
+    let player = Player::new().unwrap();
+    assert!(player.play(params).is_ok());
+    assert!(player.stop().is_ok());
+}
+
+#[test]
+fn test_pluck_voice() {
+    use crate::pluck::PluckVoice;
+    use crate::sound::Transform;
+    use crate::voice::{Engine, Voice, MAX_SECONDS};
+
+    let energy = |buffer: &[f32]| buffer.iter().map(|s| s * s).sum::<f32>();
+    let tail = |voice: &PluckVoice| {
+        let buffer = voice.render();
+        assert_eq!(buffer.len(), voice.duration_samples());
+        energy(&buffer[buffer.len() / 2..]) / energy(&buffer)
+    };
+
+    let mut voice = PluckVoice::new(220.0);
+    let ringing = tail(&voice);
+    voice.damping = 1.0;
+    // A damped string dies away sooner
+    assert!(tail(&voice) < ringing);
+
+    let mut bright = PluckVoice::new(220.0);
+    bright.brightness = 1.0;
+    assert_ne!(bright.render(), PluckVoice::new(220.0).render());
+
+    // Deterministic and normalized
+    let buffer = voice.render();
+    assert_eq!(buffer, voice.render());
+    let peak = buffer.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+    assert!((peak - 1.0).abs() < 1e-6);
+
+    voice.set_note_length(0.1);
+    assert_eq!(voice.duration_samples(), 6615);
+    let mut long = voice.clone();
+    long.set_note_length(1e7);
+    assert!(
+        long.duration_samples() < crate::mixer::ms_to_samples(2.0 * MAX_SECONDS * 1000.0) as usize
+    );
+
+    let params = SoundParams::from_engine(Engine::Pluck(voice));
+    let longer = params.transformed(&Transform {
+        transpose: -12.0,
+        time_scale: 2.0,
+    });
+    assert!((longer.freq() - 110.0).abs() < 1e-9);
+    assert_eq!(longer.duration_samples(), 13230);
+
+    let player = Player::new().unwrap();
+    assert!(player.play(params).is_ok());
+    assert!(player.stop().is_ok());
+}
+
+#[test]
+fn test_wavetable() {
+    let mut sample = Sample::new();
+    sample.env_attack = 0.0;
+    sample.env_sustain = 0.3;
+    sample.env_decay = 0.2;
+    let plain = SoundParams::new(sample);
+
+    // A Game Boy style 4-bit ramp
+    let ramp: Vec<f32> = (0..32).map(|i| (i / 2) as f32).collect();
+    let params = plain.clone().with_wavetable(&ramp);
+    let buffer = params.render();
+    assert_eq!(buffer.len(), plain.duration_samples());
+    assert!(buffer.iter().all(|s| s.abs() <= 1.0));
+    assert!(buffer.iter().any(|s| s.abs() > 0.05));
+    assert_ne!(buffer, params.clone().with_wavetable(&[15.0, 0.0]).render());
+
+    // A flat table is silent
+    let flat = plain.with_wavetable(&[7.0; 32]).render();
+    assert!(flat.iter().all(|s| *s == 0.0));
+
+    // Transforms still apply to the sfxr envelope and pitch
+    let shorter = params.transformed(&crate::sound::Transform {
+        transpose: 12.0,
+        time_scale: 0.5,
+    });
+    assert!(shorter.render().len() < buffer.len());
+
+    // Unknown wave types are rejected rather than played as square
+    assert!(SoundParams::from_json(r#"{ "wave_type": 9 }"#).is_err());
+}
+
+#[test]
+fn test_nes_voice() {
+    use crate::nes::{noise_freq, Channel, NesVoice};
+    use crate::voice::{Engine, Voice, MAX_SECONDS};
+
+    let levels = |voice: &NesVoice| {
+        let buffer = voice.render();
+        assert_eq!(buffer.len(), voice.duration_samples());
+        assert!(buffer.iter().all(|s| s.abs() <= 1.0));
+        buffer
+    };
+
+    // Each hardware duty cycle has its own timbre
+    let mut pulse = NesVoice::new(Channel::Pulse, 440.0);
+    let half = levels(&pulse);
+    pulse.duty = 0;
+    assert_ne!(levels(&pulse), half);
+
+    // The triangle ignores volume, the pulse goes silent at level 0
+    let mut triangle = NesVoice::new(Channel::Triangle, 440.0);
+    triangle.level = 0;
+    assert!(levels(&triangle).iter().any(|s| s.abs() > 0.1));
+    pulse.level = 0;
+    assert!(levels(&pulse).iter().all(|s| *s == 0.0));
+
+    // Short mode noise repeats every 93 steps, long mode doesn't
+    let mut noise = NesVoice::new(Channel::Noise, noise_freq(0));
+    let long = levels(&noise);
+    noise.short_noise = true;
+    assert_ne!(levels(&noise), long);
+
+    // A decaying envelope ends the note early
+    noise.envelope = Some(0);
+    noise.set_note_length(1.0);
+    assert_eq!(
+        noise.duration_samples(),
+        crate::mixer::ms_to_samples(1000.0 * 16.0 / 240.0) as usize
+    );
+
+    let mut held = NesVoice::new(Channel::Triangle, 220.0);
+    held.set_note_length(1e7);
+    assert_eq!(
+        held.duration_samples(),
+        crate::mixer::ms_to_samples(MAX_SECONDS * 1000.0) as usize
+    );
+
+    let params = SoundParams::from_engine(Engine::Nes(NesVoice::new(Channel::Pulse, 440.0)));
+    let player = Player::new().unwrap();
+    assert!(player.play(params).is_ok());
+    assert!(player.stop().is_ok());
+}
+
+#[test]
+fn test_bfxr_extensions() {
+    use crate::synth::{Extended, Waveform};
+
+    let mut sample = Sample::new();
+    sample.env_attack = 0.0;
+    sample.env_sustain = 0.2;
+    sample.env_decay = 0.1;
+    let plain = SoundParams::new(sample);
+    let render = |extended: Extended| {
+        let buffer = plain.clone().with_extended(extended).render();
+        assert_eq!(buffer.len(), plain.duration_samples());
+        assert!(buffer.iter().all(|s| s.abs() <= 1.0));
+        buffer
+    };
+
+    // Every bfxr waveform sounds, and differently from the others
+    let mut renders: Vec<Vec<f32>> = Vec::new();
+    for index in 5..=10 {
+        let buffer = render(Extended {
+            waveform: Some(Waveform::from_index(index).unwrap()),
+            ..Default::default()
+        });
+        assert!(
+            buffer.iter().any(|s| s.abs() > 0.01),
+            "wave {} is silent",
+            index
+        );
+        assert!(renders.iter().all(|other| *other != buffer));
+        renders.push(buffer);
+    }
+    assert!(Waveform::from_index(11).is_err());
+    assert!(matches!(
+        Waveform::from_index(2).unwrap().sfxr(),
+        Some(sfxr::WaveType::Sine)
+    ));
+
+    let square = render(Extended {
+        waveform: Some(Waveform::Square),
+        ..Default::default()
+    });
+    let overtones = render(Extended {
+        waveform: Some(Waveform::Square),
+        overtones: 3,
+        overtone_falloff: 0.5,
+        ..Default::default()
+    });
+    assert_ne!(square, overtones);
+
+    // Heavy bit crush holds each value for several samples
+    let crushed = render(Extended {
+        waveform: Some(Waveform::Square),
+        bit_crush: 0.9,
+        ..Default::default()
+    });
+    let repeats = crushed.windows(2).filter(|w| w[0] == w[1]).count();
+    assert!(repeats > crushed.len() / 2);
+
+    // Compression lifts quiet parts of the envelope
+    let energy = |buffer: &[f32]| buffer.iter().map(|s| s * s).sum::<f32>();
+    let compressed = render(Extended {
+        waveform: Some(Waveform::Square),
+        compression: 1.0,
+        ..Default::default()
+    });
+    assert!(energy(&compressed) > energy(&square));
+
+    // The crush sweep slows down with the rest of the sound
+    let sweeping = plain.clone().with_extended(Extended {
+        bit_crush_sweep: 0.8,
+        ..Default::default()
+    });
+    let slow = sweeping.transformed(&crate::sound::Transform {
+        transpose: 0.0,
+        time_scale: 2.0,
+    });
+    assert_eq!(slow.extended().bit_crush_sweep, 0.4);
+}
+
+#[test]
+fn test_bfxr_strings() {
+    use crate::bfxr;
+
+    // A breaker laser with bit crush
+    let laser =
+        "8,0.5,0,0.2,0.1,0.3,0.3,0.6,0.2,-0.3,0,0,0,0.2,0.5,0,0,0,0,0,0.4,0,0,0,0,1,0,0,0,0,0.3,0";
+    assert!(bfxr::is_bfxr(laser));
+    assert!(!bfxr::is_bfxr(r#"{ "wave_type": 1 }"#));
+
+    let (params, unsupported) = bfxr::parse(laser).unwrap();
+    assert!(unsupported.is_empty());
+    assert_eq!(params.volume(), 0.25);
+    let buffer = params.render();
+    assert_eq!(buffer.len(), params.duration_samples());
+    assert!(buffer.iter().any(|s| s.abs() > 0.01));
+
+    // A second pitch jump and extra fields are reported, not silently dropped
+    let jump = laser.replace(",0,0,0,0,0.4,", ",0,0,0.5,0.2,0.4,");
+    let (_, unsupported) = bfxr::parse(&format!("{},1", jump)).unwrap();
+    assert_eq!(unsupported, vec!["Pitch Jump Amount 2", "Field 33"]);
+
+    // Strings from older versions may stop early
+    assert!(bfxr::parse("0,0.5,0,0.3,0,0.4").is_ok());
+    assert!(bfxr::parse("0,loud").is_err());
+    assert!(bfxr::parse("0,0.5,nan").is_err());
+
+    // Out of range sliders are clamped, so huge envelopes and harmonics can't hang
+    let (huge, _) = bfxr::parse("0,0.5,0,1e8,0,1e8,5,0.3,0,0,0,0,0,1e8,-3").unwrap();
+    let (full, _) = bfxr::parse("0,0.5,0,1,0,1,1,0.3,0,0,0,0,0,1,0").unwrap();
+    assert_eq!(huge.duration_samples(), full.duration_samples());
+    assert_eq!(huge.render(), full.render());
+}
+
+#[test]
+fn test_share_links() {
+    use crate::share;
+    use crate::sound::SoundParams;
+    use sfxr::{Sample, WaveType};
+
+    let mut sample = Sample::new();
+    sample.wave_type = WaveType::Sawtooth;
+    sample.env_attack = 0.25;
+    sample.base_freq = 0.5;
+    sample.freq_ramp = -0.125;
+    sample.arp_mod = 0.75;
+    sample.hpf_ramp = 0.0625;
+    let link = share::encode(&SoundParams::new(sample)).unwrap();
+    assert!(link.starts_with(share::URL));
+
+    // The full link, its fragment and the bare hash all decode to the same sound
+    let hash = &link[share::URL.len()..];
+    for text in [link.clone(), format!("#{}", hash), hash.to_string()] {
+        assert!(share::is_share_link(&text));
+        let decoded = share::decode(&text).unwrap();
+        let decoded = decoded.sample();
+        assert!(matches!(decoded.wave_type, WaveType::Sawtooth));
+        assert_eq!(decoded.env_attack, 0.25);
+        assert_eq!(decoded.base_freq, 0.5);
+        assert_eq!(decoded.freq_ramp, -0.125);
+        assert_eq!(decoded.arp_mod, 0.75);
+        assert_eq!(decoded.hpf_ramp, 0.0625);
+    }
+
+    // Only plain sfxr sounds fit in a link
+    let table = SoundParams::new(Sample::new()).with_wavetable(&[0.0, 1.0, 0.0, -1.0]);
+    assert!(share::encode(&table).is_err());
+
+    assert!(!share::is_share_link("https://sfxr.me/#not-base58!"));
+    assert!(share::decode("3mJr7AoUXx2Wqd").is_err());
+}
+
+#[test]
+fn test_sfs_files() {
+    use crate::sfs;
+    use crate::sound::SoundParams;
+    use sfxr::{Sample, WaveType};
+
+    let mut sample = Sample::new();
+    sample.wave_type = WaveType::Noise;
+    sample.base_freq = 0.25;
+    sample.freq_dramp = -0.5;
+    sample.env_punch = 0.75;
+    sample.lpf_resonance = 0.125;
+    sample.arp_mod = 0.5;
+    let data = sfs::write(&SoundParams::new(sample).with_volume(0.5)).unwrap();
+    // Version, wave type, 24 floats and the filter switch
+    assert_eq!(data.len(), 4 + 4 + 24 * 4 + 1);
+
+    let params = sfs::parse(&data).unwrap();
+    assert_eq!(params.volume(), 0.5);
+    let sample = params.sample();
+    assert!(matches!(sample.wave_type, WaveType::Noise));
+    assert_eq!(sample.base_freq, 0.25);
+    assert_eq!(sample.freq_dramp, -0.5);
+    assert_eq!(sample.env_punch, 0.75);
+    assert_eq!(sample.lpf_resonance, 0.125);
+    assert_eq!(sample.arp_mod, 0.5);
+
+    // Version 100 has no volume, delta slide or arpeggio
+    let mut old = 100i32.to_le_bytes().to_vec();
+    old.extend_from_slice(&1i32.to_le_bytes());
+    for i in 0..20 {
+        old.extend_from_slice(&(i as f32 / 100.0).to_le_bytes());
+        if i == 11 {
+            old.push(1);
+        }
+    }
+    let params = sfs::parse(&old).unwrap();
+    assert_eq!(params.volume(), 0.5);
+    let sample = params.sample();
+    assert!(matches!(sample.wave_type, WaveType::Sawtooth));
+    assert_eq!(sample.freq_ramp, 0.02f32 as f64);
+    assert_eq!(sample.duty, 0.03);
+    assert_eq!(sample.lpf_resonance, 0.12);
+    assert_eq!(sample.repeat_speed, 0.19);
+
+    let mut future = data.clone();
+    future[0] = 103;
+    assert!(sfs::parse(&future).is_err());
+    assert!(sfs::parse(&data[..40]).is_err());
+    assert!(sfs::write(&SoundParams::new(Sample::new()).with_wavetable(&[0.0, 1.0])).is_err());
+}
+
+#[test]
+fn test_json_export() {
+    use crate::switch::{Stroke, SwitchKind, SwitchVoice};
+    use crate::voice::Engine;
+
+    let params = SoundParams::new(Sample::laser(Some(42))).with_volume(0.4);
+    let json = params.to_json().unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["oldParams"], true);
+    assert_eq!(value["sample_rate"], 44100);
+
+    // Every parameter survives the round trip exactly
+    let back = SoundParams::from_json(&json).unwrap();
+    assert_eq!(back.volume(), 0.4);
+    assert_eq!(back.to_json().unwrap(), json);
+    let (a, b) = (params.sample(), back.sample());
+    assert_eq!(a.base_freq, b.base_freq);
+    assert_eq!(a.freq_ramp, b.freq_ramp);
+    assert_eq!(a.duty, b.duty);
+    assert_eq!(a.env_decay, b.env_decay);
+    assert_eq!(a.hpf_freq, b.hpf_freq);
+
+    let switch = SoundParams::from_engine(Engine::Switch(SwitchVoice::new(
+        SwitchKind::Clicky,
+        Stroke::Press,
+    )));
+    assert!(switch.to_json().is_err());
+}
+
+#[test]
+fn test_unit_round_trips() {
+    use crate::units::*;
+
+    type Conversion = (&'static str, fn(f64) -> f64, fn(f64) -> f64, f64);
+    // Name, human units to param, param to human units, lowest param
+    let conversions: [Conversion; 19] = [
+        ("base_freq", freq_to_param, param_to_freq, 0.05),
+        ("env_punch", punch_to_param, param_to_punch, -1.0),
+        ("freq_ramp", freq_ramp_to_param, param_to_freq_ramp, -1.0),
+        ("freq_dramp", freq_dramp_to_param, param_to_freq_dramp, -1.0),
+        ("vib_speed", vib_speed_to_param, param_to_vib_speed, 0.0),
+        (
+            "vib_strength",
+            vib_strength_to_param,
+            param_to_vib_strength,
+            0.0,
+        ),
+        ("arp_mod", arp_mod_to_param, param_to_arp_mod, -1.0),
+        ("arp_speed", arp_speed_to_param, param_to_arp_speed, 0.0),
+        (
+            "repeat_speed",
+            repeat_speed_to_param,
+            param_to_repeat_speed,
+            0.0,
+        ),
+        ("duty", duty_to_param, param_to_duty, 0.0),
+        ("duty_ramp", duty_ramp_to_param, param_to_duty_ramp, -1.0),
+        ("pha_offset", pha_offset_to_param, param_to_pha_offset, -1.0),
+        ("pha_ramp", pha_ramp_to_param, param_to_pha_ramp, -1.0),
+        ("lpf_freq", lpf_freq_to_param, param_to_lpf_freq, 0.0),
+        ("lpf_ramp", lpf_ramp_to_param, param_to_lpf_ramp, -1.0),
+        (
+            "lpf_resonance",
+            lpf_resonance_to_param,
+            param_to_lpf_resonance,
+            0.0,
+        ),
+        ("hpf_freq", hpf_freq_to_param, param_to_hpf_freq, 0.0),
+        ("hpf_ramp", hpf_ramp_to_param, param_to_hpf_ramp, -1.0),
+        ("freq_limit", freq_to_param, param_to_freq, 0.05),
+    ];
+
+    for (name, to_param, from_param, lowest) in conversions {
+        for step in 0..=40 {
+            let param = lowest + (1.0 - lowest) * step as f64 / 40.0;
+            let back = to_param(from_param(param));
+            assert!(
+                (back - param).abs() < 1e-6,
+                "{}: {} came back as {}",
+                name,
+                param,
+                back
+            );
+        }
+    }
+
+    for step in 0..=20 {
+        let param = step as f32 / 20.0;
+        assert!((seconds_to_env(env_to_seconds(param)) - param).abs() < 1e-5);
+    }
+
+    // A rising slide of one octave per second
+    assert!(freq_ramp_to_param(1.0) > 0.0);
+    assert!((param_to_freq_ramp(freq_ramp_to_param(1.0)) - 1.0).abs() < 1e-6);
+
+    // Which renders a rising pitch
+    let crossings = |buffer: &[f32]| {
+        buffer
+            .windows(2)
+            .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+            .count()
+    };
+    let mut sample = Sample::new();
+    sample.freq_ramp = freq_ramp_to_param(1.0);
+    let rising = SoundParams::new(sample).with_freq(440.0).render();
+    let quarter = rising.len() / 4;
+    assert!(crossings(&rising[2 * quarter..3 * quarter]) > crossings(&rising[..quarter]));
+
+    // Arpeggios shorter than the 32 sample minimum jump as soon as they can
+    let shortest = arp_speed_to_param(0.0005);
+    assert!(shortest < 1.0 && (shortest as f32) < 1.0);
+    assert!((param_to_arp_speed(shortest) - 32.0 / 44100.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_validation() {
+    use crate::validate::{self, Field, Issue};
+
+    let field = |name: &str, value: Field| (name.to_string(), value);
+    let valid = [
+        field("wave_type", Field::Number(1.0)),
+        field("base_freq", Field::Text("A4".into())),
+        field("env_decay", Field::Number(0.3)),
+        field("volume", Field::Number(0.5)),
+    ];
+    assert!(validate::check(&valid).is_empty());
+
+    let issues = validate::check(&[
+        field("base_frq", Field::Number(440.0)),
+        field("env_decay", Field::Number(5.0)),
+        field("duty", Field::Text("half".into())),
+        field("wave_type", Field::Number(12.0)),
+        field("overtones", Field::Number(2.5)),
+    ]);
+    assert_eq!(issues.len(), 5);
+    // Sorted by field
+    assert_eq!(
+        issues[0],
+        Issue::Unknown {
+            field: "base_frq".into(),
+            suggestion: Some("base_freq"),
+        }
+    );
+    assert_eq!(
+        issues[0].message(),
+        "Unknown field 'base_frq', did you mean 'base_freq'?"
+    );
+    assert!(matches!(
+        &issues[1],
+        Issue::Type { field, expected: "number", got: "string" } if field == "duty"
+    ));
+    match &issues[2] {
+        Issue::Clamped {
+            field,
+            value,
+            clamped,
+        } => {
+            assert_eq!(field, "env_decay");
+            assert_eq!(*value, 5.0);
+            assert!((clamped - 100000.0 / 44100.0).abs() < 1e-3);
+        }
+        other => panic!("expected a clamped env_decay, got {:?}", other),
+    }
+    assert!(matches!(
+        &issues[3],
+        Issue::Type {
+            expected: "integer",
+            ..
+        }
+    ));
+    assert!(matches!(&issues[4], Issue::Invalid { field, .. } if field == "wave_type"));
+
+    // Nothing close enough to suggest
+    let issues = validate::check(&[field("colour", Field::Number(1.0))]);
+    assert_eq!(
+        issues,
+        vec![Issue::Unknown {
+            field: "colour".into(),
+            suggestion: None,
+        }]
+    );
+
+    // Engines take their own ranges: a 75% NES duty is not an sfxr duty
+    let nes = [
+        field("engine", Field::Text("nes".into())),
+        field("duty", Field::Number(75.0)),
+        field("level", Field::Number(20.0)),
+    ];
+    let issues = validate::check(&nes);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].field(), "level");
+
+    let fm = [
+        field("engine", Field::Text("fm".into())),
+        field("algorithm", Field::Text("stacked".into())),
+        field(
+            "operators",
+            Field::List(vec![
+                Field::Table(vec![field("ratio", Field::Number(1.0))]),
+                Field::Table(vec![field("ratoi", Field::Number(2.0))]),
+            ]),
+        ),
+    ];
+    let issues = validate::check(&fm);
+    assert!(matches!(&issues[0], Issue::Invalid { field, .. } if field == "algorithm"));
+    assert_eq!(
+        issues[1],
+        Issue::Unknown {
+            field: "operators[2].ratoi".into(),
+            suggestion: Some("ratio"),
+        }
+    );
+}
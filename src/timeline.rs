@@ -0,0 +1,161 @@
+use crate::mixer;
+use crate::sound::{SoundParams, Transform};
+use mlua::prelude::*;
+use std::collections::BTreeMap;
+
+/// Longest timeline that will be rendered, ten minutes.
+pub const MAX_MS: f64 = 600_000.0;
+
+pub struct TimelineEntry {
+    pub sound: SoundParams,
+    /// Start offset in milliseconds, `None` starts when the previous entry ends.
+    pub at_ms: Option<f64>,
+    pub gain: f32,
+}
+
+impl TimelineEntry {
+    pub fn new(sound: SoundParams) -> Self {
+        Self {
+            sound,
+            at_ms: None,
+            gain: 1.0,
+        }
+    }
+
+    pub fn at(mut self, at_ms: f64) -> Self {
+        self.at_ms = Some(at_ms);
+        self
+    }
+
+    pub fn with_gain(mut self, gain: f32) -> Self {
+        self.gain = gain;
+        self
+    }
+}
+
+/// A set of sounds mixed offline into a single buffer. Sounds with a glide bend
+/// from the pitch of the latest entry to start before them.
+#[derive(Default)]
+pub struct Timeline {
+    entries: Vec<TimelineEntry>,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, entry: TimelineEntry) {
+        self.entries.push(entry);
+    }
+
+    pub fn render(&self, transform: &Transform) -> Result<Vec<f32>, String> {
+        let mut out: Vec<f32> = Vec::new();
+        let mut cursor = 0;
+        // Pitch of the last entry to start at each offset
+        let mut pitches = BTreeMap::new();
+
+        for entry in &self.entries {
+            let sound = entry.sound.transformed(transform);
+            let start = match entry.at_ms {
+                Some(ms) => mixer::ms_to_samples(ms * transform.time_scale) as usize,
+                None => cursor,
+            };
+            // Only glide from a note that started earlier, not from others in a chord
+            let buffer = match pitches.range(..start).next_back() {
+                Some((_, from_hz)) if sound.glide_ms() > 0.0 => sound.render_glide(*from_hz),
+                _ => sound.render(),
+            };
+            pitches.insert(start, sound.freq());
+            cursor = mix_into(&mut out, &buffer, start, entry.gain)?;
+        }
+
+        for sample in out.iter_mut() {
+            *sample = sample.clamp(-1.0, 1.0);
+        }
+        Ok(out)
+    }
+}
+
+/// Add `buffer` scaled by `gain` into `out` at `start`, growing `out` as needed, and
+/// return where it ends. Fails rather than grow `out` past `MAX_MS`.
+pub fn mix_into(
+    out: &mut Vec<f32>,
+    buffer: &[f32],
+    start: usize,
+    gain: f32,
+) -> Result<usize, String> {
+    let end = start
+        .checked_add(buffer.len())
+        .filter(|end| *end as u64 <= mixer::ms_to_samples(MAX_MS))
+        .ok_or_else(|| format!("timeline is longer than {} ms", MAX_MS))?;
+    if out.len() < end {
+        out.resize(end, 0.0);
+    }
+    for (slot, sample) in out[start..end].iter_mut().zip(buffer) {
+        *slot += sample * gain;
+    }
+    Ok(end)
+}
+
+/// How a rendered timeline is started, read from the `append` and `wait` option flags.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PlayMode {
+    /// Start immediately on the mixer.
+    #[default]
+    Play,
+    /// Queue after current sounds.
+    Append,
+    /// Queue and block until finished.
+    Wait,
+}
+
+impl FromLua for PlayMode {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let LuaValue::Table(table) = value else {
+            return Ok(PlayMode::Play);
+        };
+        if table.get::<Option<bool>>("wait")?.unwrap_or(false) {
+            Ok(PlayMode::Wait)
+        } else if table.get::<Option<bool>>("append")?.unwrap_or(false) {
+            Ok(PlayMode::Append)
+        } else {
+            Ok(PlayMode::Play)
+        }
+    }
+}
+
+impl FromLua for TimelineEntry {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Table(table) if table.contains_key("sound")? => {
+                let mut entry = TimelineEntry::new(table.get("sound")?);
+                if let Some(at_ms) = table.get::<Option<f64>>("at_ms")? {
+                    if !at_ms.is_finite() || at_ms > MAX_MS {
+                        return Err(mlua::Error::RuntimeError(format!(
+                            "at_ms must be a finite number up to {}, got {}",
+                            MAX_MS, at_ms
+                        )));
+                    }
+                    entry = entry.at(at_ms);
+                }
+                if let Some(gain) = table.get::<Option<f32>>("gain")? {
+                    entry = entry.with_gain(gain.max(0.0));
+                }
+                Ok(entry)
+            }
+            // A bare sound plays right after the previous entry
+            value => Ok(TimelineEntry::new(SoundParams::from_lua(value, lua)?)),
+        }
+    }
+}
+
+impl FromLua for Timeline {
+    fn from_lua(value: LuaValue, lua: &Lua) -> LuaResult<Self> {
+        let mut timeline = Timeline::new();
+        for entry in Vec::<TimelineEntry>::from_lua(value, lua)? {
+            timeline.push(entry);
+        }
+        Ok(timeline)
+    }
+}
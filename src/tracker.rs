@@ -0,0 +1,459 @@
+// ProTracker 4 channel .mod loader and player, streamed as a mixer voice
+
+use crate::mixer::SAMPLE_RATE;
+use mlua::prelude::*;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ModError {
+    #[error("Failed to read module: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid module: {0}")]
+    Invalid(String),
+    #[error("Unsupported module: {0}")]
+    Unsupported(String),
+}
+
+const CHANNELS: usize = 4;
+const ROWS: usize = 64;
+const PAL_CLOCK: f64 = 7_093_789.2;
+const MIN_PERIOD: i32 = 113;
+const MAX_PERIOD: i32 = 856;
+
+const VIBRATO_TABLE: [i32; 32] = [
+    0, 24, 49, 74, 97, 120, 141, 161, 180, 197, 212, 224, 235, 244, 250, 253, 255, 253, 250, 244,
+    235, 224, 212, 197, 180, 161, 141, 120, 97, 74, 49, 24,
+];
+
+struct Instrument {
+    data: Vec<f32>,
+    finetune: i8,
+    volume: u8,
+    loop_start: usize,
+    loop_len: usize,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Cell {
+    period: u16,
+    instrument: u8,
+    effect: u8,
+    param: u8,
+}
+
+type Pattern = Vec<[Cell; CHANNELS]>;
+
+pub struct Module {
+    instruments: Vec<Instrument>,
+    orders: Vec<u8>,
+    restart: usize,
+    patterns: Vec<Pattern>,
+}
+
+fn be_u16(data: &[u8], offset: usize) -> usize {
+    u16::from_be_bytes([data[offset], data[offset + 1]]) as usize
+}
+
+impl Module {
+    pub fn load(path: &str) -> Result<Module, ModError> {
+        Module::parse(&std::fs::read(path)?)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Module, ModError> {
+        const HEADER_LEN: usize = 1084;
+        if data.len() < HEADER_LEN {
+            return Err(ModError::Invalid("file too short".into()));
+        }
+
+        match &data[1080..1084] {
+            b"M.K." | b"M!K!" | b"FLT4" | b"4CHN" => {}
+            tag => {
+                return Err(ModError::Unsupported(format!(
+                    "format tag '{}', only 4 channel modules are supported",
+                    String::from_utf8_lossy(tag)
+                )))
+            }
+        }
+
+        let song_len = (data[950] as usize).clamp(1, 128);
+        let restart = data[951] as usize;
+        let orders = data[952..952 + song_len].to_vec();
+        let pattern_count = data[952..1080].iter().max().copied().unwrap_or(0) as usize + 1;
+
+        let mut offset = HEADER_LEN;
+        let mut patterns = Vec::with_capacity(pattern_count);
+        for _ in 0..pattern_count {
+            let end = offset + ROWS * CHANNELS * 4;
+            let bytes = data
+                .get(offset..end)
+                .ok_or_else(|| ModError::Invalid("truncated pattern data".into()))?;
+            let pattern = bytes
+                .chunks_exact(CHANNELS * 4)
+                .map(|row| {
+                    let mut cells = [Cell::default(); CHANNELS];
+                    for (cell, b) in cells.iter_mut().zip(row.chunks_exact(4)) {
+                        *cell = Cell {
+                            period: (((b[0] & 0x0F) as u16) << 8) | b[1] as u16,
+                            instrument: (b[0] & 0xF0) | (b[2] >> 4),
+                            effect: b[2] & 0x0F,
+                            param: b[3],
+                        };
+                    }
+                    cells
+                })
+                .collect();
+            patterns.push(pattern);
+            offset = end;
+        }
+
+        let mut instruments = Vec::with_capacity(31);
+        for i in 0..31 {
+            let header = 20 + i * 30;
+            let len = be_u16(data, header + 22) * 2;
+            let loop_start = be_u16(data, header + 26) * 2;
+            let loop_len = be_u16(data, header + 28) * 2;
+
+            // Some files end before the last sample, keep whatever is there
+            let end = (offset + len).min(data.len());
+            let samples: Vec<f32> = data[offset.min(end)..end]
+                .iter()
+                .map(|b| *b as i8 as f32 / 128.0)
+                .collect();
+            offset += len;
+
+            let looped = loop_len > 2 && loop_start < samples.len();
+            instruments.push(Instrument {
+                finetune: (((data[header + 24] & 0x0F) << 4) as i8) >> 4,
+                volume: data[header + 25].min(64),
+                loop_start: if looped { loop_start } else { 0 },
+                loop_len: if looped {
+                    loop_len.min(samples.len() - loop_start)
+                } else {
+                    0
+                },
+                data: samples,
+            });
+        }
+
+        Ok(Module {
+            instruments,
+            orders,
+            restart,
+            patterns,
+        })
+    }
+}
+
+#[derive(Default)]
+struct Channel {
+    instrument: Option<usize>,
+    pos: f64,
+    playing: bool,
+    period: i32,
+    output_period: i32,
+    target_period: i32,
+    porta_speed: i32,
+    volume: i32,
+    vibrato_pos: usize,
+    vibrato_speed: usize,
+    vibrato_depth: i32,
+    effect: u8,
+    param: u8,
+}
+
+impl Channel {
+    fn slide_volume(&mut self, param: u8) {
+        let up = (param >> 4) as i32;
+        let down = (param & 0x0F) as i32;
+        self.volume = (self.volume + if up > 0 { up } else { -down }).clamp(0, 64);
+    }
+
+    fn tone_portamento(&mut self) {
+        if self.target_period == 0 {
+            return;
+        }
+        if self.period < self.target_period {
+            self.period = (self.period + self.porta_speed).min(self.target_period);
+        } else {
+            self.period = (self.period - self.porta_speed).max(self.target_period);
+        }
+    }
+
+    fn vibrato(&mut self) {
+        let delta = VIBRATO_TABLE[self.vibrato_pos & 31] * self.vibrato_depth / 128;
+        let delta = if self.vibrato_pos & 32 == 0 {
+            delta
+        } else {
+            -delta
+        };
+        self.output_period = self.period + delta;
+        self.vibrato_pos = (self.vibrato_pos + self.vibrato_speed) & 63;
+    }
+}
+
+/// Streams a module as mono samples. Ends after the last order unless looping.
+pub struct ModPlayer {
+    module: Arc<Module>,
+    channels: [Channel; CHANNELS],
+    order: usize,
+    row: usize,
+    tick: u32,
+    speed: u32,
+    samples_per_tick: f64,
+    tick_remaining: f64,
+    // (order, row) to continue from after the current row
+    jump: Option<(usize, usize)>,
+    looping: bool,
+    gain: f32,
+    finished: bool,
+}
+
+impl ModPlayer {
+    pub fn new(module: Arc<Module>, looping: bool, gain: f32) -> Self {
+        Self {
+            module,
+            channels: Default::default(),
+            order: 0,
+            row: 0,
+            tick: 0,
+            speed: 6,
+            samples_per_tick: SAMPLE_RATE as f64 * 2.5 / 125.0,
+            tick_remaining: 0.0,
+            jump: None,
+            looping,
+            gain,
+            finished: false,
+        }
+    }
+
+    fn process_row(&mut self) {
+        let pattern = self.module.orders[self.order] as usize;
+        let cells = self.module.patterns[pattern][self.row];
+
+        for (index, cell) in cells.iter().enumerate() {
+            let module = &self.module;
+            let channel = &mut self.channels[index];
+            channel.effect = cell.effect;
+            channel.param = cell.param;
+
+            if cell.instrument > 0 {
+                let instrument = cell.instrument as usize - 1;
+                if let Some(sample) = module.instruments.get(instrument) {
+                    channel.instrument = Some(instrument);
+                    channel.volume = sample.volume as i32;
+                }
+            }
+
+            if cell.period > 0 {
+                let finetune = channel
+                    .instrument
+                    .map(|i| module.instruments[i].finetune)
+                    .unwrap_or(0);
+                let period =
+                    (cell.period as f64 * (-(finetune as f64) / 96.0).exp2()).round() as i32;
+                if cell.effect == 0x3 || cell.effect == 0x5 {
+                    channel.target_period = period;
+                } else {
+                    channel.period = period;
+                    channel.pos = 0.0;
+                    channel.playing = true;
+                    channel.vibrato_pos = 0;
+                }
+            }
+
+            match (cell.effect, cell.param) {
+                (0x3, param) if param > 0 => channel.porta_speed = param as i32,
+                (0x4, param) => {
+                    if param >> 4 > 0 {
+                        channel.vibrato_speed = (param >> 4) as usize;
+                    }
+                    if param & 0x0F > 0 {
+                        channel.vibrato_depth = (param & 0x0F) as i32;
+                    }
+                }
+                (0x9, param) if cell.period > 0 => channel.pos = param as f64 * 256.0,
+                (0xB, param) => self.jump = Some((param as usize, 0)),
+                (0xC, param) => channel.volume = param.min(64) as i32,
+                (0xD, param) => {
+                    let row = ((param >> 4) * 10 + (param & 0x0F)) as usize;
+                    let order = self.jump.map(|(order, _)| order).unwrap_or(self.order + 1);
+                    self.jump = Some((order, row.min(ROWS - 1)));
+                }
+                (0xE, param) => match param >> 4 {
+                    0x1 => {
+                        channel.period = (channel.period - (param & 0x0F) as i32).max(MIN_PERIOD)
+                    }
+                    0x2 => {
+                        channel.period = (channel.period + (param & 0x0F) as i32).min(MAX_PERIOD)
+                    }
+                    0xA => channel.volume = (channel.volume + (param & 0x0F) as i32).min(64),
+                    0xB => channel.volume = (channel.volume - (param & 0x0F) as i32).max(0),
+                    _ => {}
+                },
+                (0xF, 0) => {}
+                (0xF, param) if param < 32 => self.speed = param as u32,
+                (0xF, param) => {
+                    self.samples_per_tick = SAMPLE_RATE as f64 * 2.5 / param as f64;
+                }
+                _ => {}
+            }
+            channel.output_period = channel.period;
+        }
+    }
+
+    fn process_effects(&mut self) {
+        let tick = self.tick;
+        for channel in self.channels.iter_mut() {
+            channel.output_period = channel.period;
+            let param = channel.param;
+            match channel.effect {
+                0x0 if param > 0 => {
+                    let semitones = match tick % 3 {
+                        1 => param >> 4,
+                        2 => param & 0x0F,
+                        _ => 0,
+                    };
+                    channel.output_period =
+                        (channel.period as f64 / (semitones as f64 / 12.0).exp2()) as i32;
+                }
+                0x1 => channel.period = (channel.period - param as i32).max(MIN_PERIOD),
+                0x2 => channel.period = (channel.period + param as i32).min(MAX_PERIOD),
+                0x3 => channel.tone_portamento(),
+                0x4 => channel.vibrato(),
+                0x5 => {
+                    channel.tone_portamento();
+                    channel.slide_volume(param);
+                }
+                0x6 => {
+                    channel.vibrato();
+                    channel.slide_volume(param);
+                }
+                0xA => channel.slide_volume(param),
+                0xE if param >> 4 == 0xC && (param & 0x0F) as u32 == tick => channel.volume = 0,
+                _ => {}
+            }
+            if matches!(channel.effect, 0x1 | 0x2 | 0x3 | 0x5) {
+                channel.output_period = channel.period;
+            }
+        }
+    }
+
+    fn advance_row(&mut self) {
+        let (order, row) = match self.jump.take() {
+            // A backwards jump is how most songs loop
+            Some((order, _)) if order <= self.order && !self.looping => {
+                self.finished = true;
+                return;
+            }
+            Some(jump) => jump,
+            None if self.row + 1 >= ROWS => (self.order + 1, 0),
+            None => (self.order, self.row + 1),
+        };
+
+        self.row = row;
+        self.order = order;
+        if self.order >= self.module.orders.len() {
+            if self.looping {
+                self.order = if self.module.restart < self.module.orders.len() {
+                    self.module.restart
+                } else {
+                    0
+                };
+            } else {
+                self.finished = true;
+            }
+        }
+    }
+
+    fn next_tick(&mut self) {
+        if self.tick == 0 {
+            self.process_row();
+        } else {
+            self.process_effects();
+        }
+
+        self.tick += 1;
+        if self.tick >= self.speed {
+            self.tick = 0;
+            self.advance_row();
+        }
+    }
+
+    fn mix(&mut self) -> f32 {
+        let mut out = 0.0;
+        for channel in self.channels.iter_mut() {
+            let Some(instrument) = channel.instrument else {
+                continue;
+            };
+            let sample = &self.module.instruments[instrument];
+            if !channel.playing || channel.output_period <= 0 || sample.data.is_empty() {
+                continue;
+            }
+
+            if sample.loop_len > 0 && channel.pos >= (sample.loop_start + sample.loop_len) as f64 {
+                channel.pos -= sample.loop_len as f64
+                    * ((channel.pos - sample.loop_start as f64) / sample.loop_len as f64).floor();
+            }
+            let Some(value) = sample.data.get(channel.pos as usize) else {
+                channel.playing = false;
+                continue;
+            };
+
+            out += value * channel.volume as f32 / 64.0;
+            let freq = PAL_CLOCK / (channel.output_period as f64 * 2.0);
+            channel.pos += freq / SAMPLE_RATE as f64;
+        }
+        out / CHANNELS as f32 * self.gain
+    }
+}
+
+impl Iterator for ModPlayer {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.tick_remaining <= 0.0 {
+            if self.finished {
+                return None;
+            }
+            self.next_tick();
+            self.tick_remaining += self.samples_per_tick;
+        }
+        self.tick_remaining -= 1.0;
+        Some(self.mix())
+    }
+}
+
+pub struct ModOptions {
+    /// Restart from the song's restart position instead of ending.
+    pub looping: bool,
+    pub gain: f32,
+}
+
+impl Default for ModOptions {
+    fn default() -> Self {
+        Self {
+            looping: false,
+            gain: 1.0,
+        }
+    }
+}
+
+impl FromLua for ModOptions {
+    fn from_lua(value: LuaValue, _: &Lua) -> LuaResult<Self> {
+        let table = match value {
+            LuaValue::Nil => return Ok(Self::default()),
+            LuaValue::Table(table) => table,
+            _ => {
+                return Err(mlua::Error::RuntimeError(
+                    "Expected table for module options".into(),
+                ))
+            }
+        };
+
+        Ok(Self {
+            looping: table.get::<Option<bool>>("loop")?.unwrap_or(false),
+            gain: table.get::<Option<f32>>("gain")?.unwrap_or(1.0).max(0.0),
+        })
+    }
+}
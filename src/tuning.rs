@@ -0,0 +1,271 @@
+// Scala tunings (.scl scales and .kbm keyboard mappings) and the A4 reference.
+// Each Player keeps its own tuning, which note names, sequencer notes and melodies
+// are converted through.
+
+use crate::pitch::{A4_FREQ, A4_MIDI};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TuningError {
+    #[error("Failed to read tuning file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid tuning file: {0}")]
+    Invalid(String),
+}
+
+// Non-comment lines of a Scala file; `!` starts a comment line
+fn lines(src: &str) -> impl Iterator<Item = &str> {
+    src.lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.starts_with('!'))
+}
+
+fn first_token(line: &str) -> &str {
+    line.split_whitespace().next().unwrap_or("")
+}
+
+/// Scale degrees in cents above the tonic, the last one being the period (usually 1200).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scale {
+    pub cents: Vec<f64>,
+}
+
+impl Scale {
+    pub fn parse(src: &str) -> Result<Scale, TuningError> {
+        let mut lines = lines(src);
+        let _description = lines.next();
+        let count = lines
+            .next()
+            .and_then(|line| first_token(line).parse::<usize>().ok())
+            .ok_or_else(|| TuningError::Invalid("missing note count".into()))?;
+        if count == 0 {
+            return Err(TuningError::Invalid("scale has no notes".into()));
+        }
+
+        let cents = lines
+            .filter(|line| !line.trim().is_empty())
+            .take(count)
+            .map(|line| parse_pitch(first_token(line)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if cents.len() != count {
+            return Err(TuningError::Invalid(format!(
+                "expected {} notes, found {}",
+                count,
+                cents.len()
+            )));
+        }
+        Ok(Scale { cents })
+    }
+
+    /// Cents above the tonic of `degree`, which may fall outside the first period.
+    pub fn cents_at(&self, degree: i64) -> f64 {
+        let size = self.cents.len() as i64;
+        let period = self.cents[self.cents.len() - 1];
+        let index = degree.rem_euclid(size) as usize;
+        let base = if index == 0 {
+            0.0
+        } else {
+            self.cents[index - 1]
+        };
+        degree.div_euclid(size) as f64 * period + base
+    }
+}
+
+// "701.955" is cents, "3/2" and "2" are ratios
+fn parse_pitch(token: &str) -> Result<f64, TuningError> {
+    let invalid = || TuningError::Invalid(format!("invalid pitch '{}'", token));
+    if token.contains('.') {
+        return token.parse::<f64>().map_err(|_| invalid());
+    }
+
+    let (num, den) = token.split_once('/').unwrap_or((token, "1"));
+    let num = num.parse::<f64>().map_err(|_| invalid())?;
+    let den = den.parse::<f64>().map_err(|_| invalid())?;
+    if num <= 0.0 || den <= 0.0 {
+        return Err(invalid());
+    }
+    Ok(1200.0 * (num / den).log2())
+}
+
+/// Longest keyboard mapping, one entry per MIDI key.
+pub const MAX_MAP_SIZE: i64 = 128;
+
+/// Which scale degree each MIDI key plays, and the frequency of the reference key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyboardMapping {
+    /// Key playing scale degree 0.
+    pub middle_note: i64,
+    pub reference_note: i64,
+    pub reference_freq: f64,
+    /// Scale degree one repetition of the mapping moves up by.
+    pub octave_degree: i64,
+    /// Degree per key from the middle note, `None` for unmapped keys. Empty maps keys linearly.
+    pub keys: Vec<Option<i64>>,
+}
+
+impl KeyboardMapping {
+    pub fn parse(src: &str) -> Result<KeyboardMapping, TuningError> {
+        let mut values = lines(src)
+            .map(first_token)
+            .filter(|token| !token.is_empty());
+        let mut next = |name: &str| {
+            values
+                .next()
+                .ok_or_else(|| TuningError::Invalid(format!("missing {}", name)))
+        };
+        let int = |token: &str, name: &str| {
+            token
+                .parse::<i64>()
+                .map_err(|_| TuningError::Invalid(format!("invalid {} '{}'", name, token)))
+        };
+
+        let size = int(next("map size")?, "map size")?;
+        if !(0..=MAX_MAP_SIZE).contains(&size) {
+            return Err(TuningError::Invalid(format!(
+                "map size {} is outside 0 to {}",
+                size, MAX_MAP_SIZE
+            )));
+        }
+        // The playable key range is informational only
+        next("first note")?;
+        next("last note")?;
+        let middle_note = int(next("middle note")?, "middle note")?;
+        let reference_note = int(next("reference note")?, "reference note")?;
+        let reference_freq = next("reference frequency")?
+            .parse::<f64>()
+            .ok()
+            .filter(|freq| *freq > 0.0)
+            .ok_or_else(|| TuningError::Invalid("invalid reference frequency".into()))?;
+        let octave_degree = int(next("octave degree")?, "octave degree")?;
+
+        let mut keys = Vec::new();
+        for _ in 0..size {
+            keys.push(match values.next() {
+                Some("x") | Some("X") => None,
+                Some(token) => Some(int(token, "mapping")?),
+                None => {
+                    return Err(TuningError::Invalid(format!(
+                        "expected {} mappings, found {}",
+                        size,
+                        keys.len()
+                    )))
+                }
+            });
+        }
+
+        Ok(KeyboardMapping {
+            middle_note,
+            reference_note,
+            reference_freq,
+            octave_degree,
+            keys,
+        })
+    }
+
+    fn mapped_degree(&self, key: i64) -> Option<i64> {
+        let offset = key - self.middle_note;
+        if self.keys.is_empty() {
+            return Some(offset);
+        }
+        let size = self.keys.len() as i64;
+        self.keys[offset.rem_euclid(size) as usize]
+            .map(|degree| degree + offset.div_euclid(size) * self.octave_degree)
+    }
+
+    /// Scale degree for `key`. Unmapped keys play the nearest mapped key below them.
+    pub fn degree(&self, key: i64) -> i64 {
+        (0..=self.keys.len() as i64)
+            .find_map(|down| self.mapped_degree(key - down))
+            .unwrap_or(key - self.middle_note)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tuning {
+    pub reference_note: f64,
+    pub reference_freq: f64,
+    /// `None` is 12-tone equal temperament.
+    pub scale: Option<Scale>,
+    pub mapping: Option<KeyboardMapping>,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self::EQUAL
+    }
+}
+
+impl Tuning {
+    const EQUAL: Tuning = Tuning {
+        reference_note: A4_MIDI,
+        reference_freq: A4_FREQ,
+        scale: None,
+        mapping: None,
+    };
+
+    /// A Scala scale, mapped linearly from middle C with A4 at the default reference
+    /// unless a keyboard mapping says otherwise.
+    pub fn from_scala(scale: Scale, mapping: Option<KeyboardMapping>) -> Self {
+        let (reference_note, reference_freq) = mapping
+            .as_ref()
+            .map(|map| (map.reference_note as f64, map.reference_freq))
+            .unwrap_or((A4_MIDI, A4_FREQ));
+        Self {
+            reference_note,
+            reference_freq,
+            scale: Some(scale),
+            mapping,
+        }
+    }
+
+    pub fn load(scl_path: &str, kbm_path: Option<&str>) -> Result<Self, TuningError> {
+        let scale = Scale::parse(&std::fs::read_to_string(scl_path)?)?;
+        let mapping = kbm_path
+            .map(|path| KeyboardMapping::parse(&std::fs::read_to_string(path)?))
+            .transpose()?;
+        Ok(Self::from_scala(scale, mapping))
+    }
+
+    // Cents of `key` above scale degree 0
+    fn key_cents(&self, scale: &Scale, key: i64) -> f64 {
+        let degree = match &self.mapping {
+            Some(mapping) => mapping.degree(key),
+            None => key - 60,
+        };
+        scale.cents_at(degree)
+    }
+
+    /// Frequency of a fractional MIDI note. Fractions interpolate between neighbouring keys.
+    pub fn midi_to_freq(&self, note: f64) -> f64 {
+        let Some(scale) = &self.scale else {
+            return self.reference_freq * ((note - self.reference_note) / 12.0).exp2();
+        };
+
+        let key = note.floor();
+        let low = self.key_cents(scale, key as i64);
+        let cents = if note > key {
+            low + (self.key_cents(scale, key as i64 + 1) - low) * (note - key)
+        } else {
+            low
+        };
+        let reference = self.key_cents(scale, self.reference_note.round() as i64);
+        self.reference_freq * ((cents - reference) / 1200.0).exp2()
+    }
+
+    /// Change the reference pitch (A4, or the .kbm reference key) and keep the scale.
+    pub fn set_reference_freq(&mut self, freq: f64) -> Result<(), String> {
+        if !freq.is_finite() || freq <= 0.0 {
+            return Err(format!(
+                "reference frequency must be positive, got {}",
+                freq
+            ));
+        }
+        self.reference_freq = freq;
+        Ok(())
+    }
+
+    /// Nearest 12-TET MIDI note for `freq` against the reference pitch.
+    pub fn freq_to_midi(&self, freq: f64) -> f64 {
+        self.reference_note + 12.0 * (freq / self.reference_freq).log2()
+    }
+}
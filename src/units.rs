@@ -0,0 +1,236 @@
+// Conversions between the units params tables use (Hz, seconds, percent) and sfxr's
+// normalised parameters, each paired with its inverse. The formulas follow how sfxr
+// turns each parameter into per-sample steps at 44.1 kHz.
+
+const RATE: f64 = 44100.0;
+
+// sfxr stores the oscillator period as 100 / (base_freq^2 + 0.001) at 8x oversampling
+pub fn freq_to_param(hz: f64) -> f64 {
+    (hz * 100.0 / (8.0 * RATE) - 0.001)
+        .max(0.0)
+        .sqrt()
+        .clamp(0.0, 1.0)
+}
+
+pub fn param_to_freq(v: f64) -> f64 {
+    (v * v + 0.001) * 8.0 * RATE / 100.0
+}
+
+// Envelope stages last value^2 * 100000 samples
+pub fn seconds_to_env(seconds: f32) -> f32 {
+    ((seconds.max(0.0) * 44100.0) / 100000.0)
+        .sqrt()
+        .clamp(0.0, 1.0)
+}
+
+pub fn env_to_seconds(v: f32) -> f32 {
+    v * v * 100000.0 / 44100.0
+}
+
+/// Punch in percent of the sustain level.
+pub fn punch_to_param(percent: f64) -> f64 {
+    (percent / 100.0).clamp(-1.0, 1.0)
+}
+
+pub fn param_to_punch(v: f64) -> f64 {
+    v * 100.0
+}
+
+/// Slide in octaves per second; sfxr multiplies the period by 1 - v^3 * 0.01 every sample.
+pub fn freq_ramp_to_param(octaves: f64) -> f64 {
+    if octaves == 0.0 {
+        return 0.0;
+    }
+    ((1.0 - (-octaves / RATE * std::f64::consts::LN_2).exp()) / 0.01)
+        .cbrt()
+        .clamp(-1.0, 1.0)
+}
+
+pub fn param_to_freq_ramp(v: f64) -> f64 {
+    -RATE * (1.0 - v.powi(3) * 0.01).log2()
+}
+
+/// Change of the slide in octaves per second squared.
+pub fn freq_dramp_to_param(octaves: f64) -> f64 {
+    (octaves * (-44101.0_f64 / RATE).exp2() / RATE / -0.000001)
+        .cbrt()
+        .clamp(-1.0, 1.0)
+}
+
+pub fn param_to_freq_dramp(v: f64) -> f64 {
+    v.powi(3) * -0.000001 * RATE * (44101.0_f64 / RATE).exp2()
+}
+
+/// Vibrato rate in Hz.
+pub fn vib_speed_to_param(hz: f64) -> f64 {
+    ((64.0 / 441000.0) * hz.max(0.0) / 0.01)
+        .sqrt()
+        .clamp(0.0, 1.0)
+}
+
+pub fn param_to_vib_speed(v: f64) -> f64 {
+    v * v * 0.01 * 441000.0 / 64.0
+}
+
+/// Vibrato depth in percent of the frequency.
+pub fn vib_strength_to_param(percent: f64) -> f64 {
+    ((percent / 100.0) / 0.5).clamp(0.0, 1.0)
+}
+
+pub fn param_to_vib_strength(v: f64) -> f64 {
+    v * 0.5 * 100.0
+}
+
+/// Arpeggio frequency multiplier; sfxr multiplies the period by 1 - v^2 * 0.9 when
+/// raising and 1 + v^2 * 10 when lowering.
+pub fn arp_mod_to_param(multiplier: f64) -> f64 {
+    if multiplier == 0.0 {
+        return 0.0;
+    }
+    let v = if (1.0 / multiplier) < 1.0 {
+        ((1.0 - (1.0 / multiplier)) / 0.9).sqrt()
+    } else {
+        -((1.0 / multiplier - 1.0) / 10.0).sqrt()
+    };
+    v.clamp(-1.0, 1.0)
+}
+
+pub fn param_to_arp_mod(v: f64) -> f64 {
+    if v >= 0.0 {
+        1.0 / (1.0 - v * v * 0.9)
+    } else {
+        1.0 / (1.0 + v * v * 10.0)
+    }
+}
+
+/// Seconds before the arpeggio jump, 0 for none. Arpeggio and repeat periods are
+/// (1 - value)^2 * 20000 + 32 samples.
+pub fn arp_speed_to_param(seconds: f64) -> f64 {
+    if seconds == 0.0 {
+        return 1.0;
+    }
+    // sfxr turns the arpeggio off at exactly 1, so the shortest period stays below it
+    (1.0 - ((seconds * RATE - 32.0).max(0.0) / 20000.0).sqrt())
+        .clamp(0.0, 1.0 - f32::EPSILON as f64)
+}
+
+pub fn param_to_arp_speed(v: f64) -> f64 {
+    if v >= 1.0 {
+        return 0.0;
+    }
+    ((1.0 - v).powi(2) * 20000.0 + 32.0) / RATE
+}
+
+/// Repeats per second, 0 for none.
+pub fn repeat_speed_to_param(hz: f64) -> f64 {
+    if hz <= 0.0 {
+        0.0
+    } else if hz > 1378.0 {
+        1.0
+    } else {
+        (1.0 - ((RATE / hz - 32.0) / 20000.0).sqrt()).clamp(0.0, 1.0)
+    }
+}
+
+pub fn param_to_repeat_speed(v: f64) -> f64 {
+    if v <= 0.0 {
+        return 0.0;
+    }
+    RATE / ((1.0 - v).powi(2) * 20000.0 + 32.0)
+}
+
+/// Square duty cycle in percent.
+pub fn duty_to_param(percent: f64) -> f64 {
+    ((0.5 - (percent / 100.0)) / 0.5).clamp(0.0, 1.0)
+}
+
+pub fn param_to_duty(v: f64) -> f64 {
+    (0.5 - v * 0.5) * 100.0
+}
+
+/// Duty sweep in percent per second.
+pub fn duty_ramp_to_param(rate: f64) -> f64 {
+    ((rate / (8.0 * RATE)) / -0.00005).clamp(-1.0, 1.0)
+}
+
+pub fn param_to_duty_ramp(v: f64) -> f64 {
+    v * -0.00005 * 8.0 * RATE
+}
+
+/// Phaser offset, as `pha_offset` takes it.
+pub fn pha_offset_to_param(samples: f64) -> f64 {
+    (samples.signum() * (samples.abs() / 1020.0).sqrt()).clamp(-1.0, 1.0)
+}
+
+pub fn param_to_pha_offset(v: f64) -> f64 {
+    v.signum() * v * v * 1020.0
+}
+
+/// Change in phaser offset, as `pha_ramp` takes it.
+pub fn pha_ramp_to_param(rate: f64) -> f64 {
+    (rate.signum() * rate.abs().sqrt()).clamp(-1.0, 1.0)
+}
+
+pub fn param_to_pha_ramp(v: f64) -> f64 {
+    v.signum() * v * v
+}
+
+/// Low-pass cutoff in Hz.
+pub fn lpf_freq_to_param(hz: f64) -> f64 {
+    (hz / (hz + 8.0 * RATE) / 0.1).cbrt().clamp(0.0, 1.0)
+}
+
+pub fn param_to_lpf_freq(v: f64) -> f64 {
+    let ratio = v.powi(3) * 0.1;
+    8.0 * RATE * ratio / (1.0 - ratio)
+}
+
+/// Low-pass cutoff multiplier per second.
+pub fn lpf_ramp_to_param(factor: f64) -> f64 {
+    if factor == 0.0 {
+        return 0.0;
+    }
+    ((factor.powf(1.0 / RATE) - 1.0) / 0.0001).clamp(-1.0, 1.0)
+}
+
+pub fn param_to_lpf_ramp(v: f64) -> f64 {
+    (1.0 + v * 0.0001).powf(RATE)
+}
+
+/// Low-pass resonance in percent.
+pub fn lpf_resonance_to_param(percent: f64) -> f64 {
+    let damping = (((1.0 - percent / 100.0) / 0.11) / 5.0).max(0.0);
+    if damping <= f64::EPSILON {
+        return 0.0;
+    }
+    ((1.0 / damping - 1.0) / 20.0)
+        .max(0.0)
+        .sqrt()
+        .clamp(0.0, 1.0)
+}
+
+pub fn param_to_lpf_resonance(v: f64) -> f64 {
+    (1.0 - 0.55 / (1.0 + 20.0 * v * v)) * 100.0
+}
+
+/// High-pass cutoff in Hz.
+pub fn hpf_freq_to_param(hz: f64) -> f64 {
+    (hz / (hz + 8.0 * RATE) / 0.1).sqrt().clamp(0.0, 1.0)
+}
+
+pub fn param_to_hpf_freq(v: f64) -> f64 {
+    let ratio = v * v * 0.1;
+    8.0 * RATE * ratio / (1.0 - ratio)
+}
+
+/// High-pass cutoff multiplier per second.
+pub fn hpf_ramp_to_param(factor: f64) -> f64 {
+    if factor == 0.0 {
+        return 0.0;
+    }
+    ((factor.powf(1.0 / RATE) - 1.0) / 0.0003).clamp(-1.0, 1.0)
+}
+
+pub fn param_to_hpf_ramp(v: f64) -> f64 {
+    (1.0 + v * 0.0003).powf(RATE)
+}
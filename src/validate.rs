@@ -0,0 +1,486 @@
+// Strict checking of params tables. `from_table` skips unknown keys and clamps values
+// into range so a sound always plays; this reports what it would have glossed over.
+
+use crate::fm::Algorithm;
+use crate::nes::Channel;
+use crate::pitch;
+use crate::switch::{Stroke, SwitchKind};
+use crate::synth::Waveform;
+use crate::units::*;
+use mlua::prelude::*;
+
+/// A params value, decoupled from Lua.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    Number(f64),
+    Text(String),
+    List(Vec<Field>),
+    Table(Vec<(String, Field)>),
+    /// Any other Lua value, by type name.
+    Other(&'static str),
+}
+
+impl Field {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Field::Number(_) => "number",
+            Field::Text(_) => "string",
+            Field::List(_) | Field::Table(_) => "table",
+            Field::Other(name) => *name,
+        }
+    }
+
+    /// Reads `depth` levels of nested tables; deeper ones, cycles included, are opaque.
+    fn from_value(value: LuaValue, depth: usize) -> LuaResult<Field> {
+        Ok(match value {
+            LuaValue::Integer(n) => Field::Number(n as f64),
+            LuaValue::Number(n) => Field::Number(n),
+            LuaValue::String(s) => Field::Text(s.to_str()?.to_string()),
+            LuaValue::Table(_) if depth == 0 => Field::Other("table"),
+            LuaValue::Table(table) if table.raw_len() > 0 => Field::List(
+                table
+                    .sequence_values::<LuaValue>()
+                    .map(|value| Field::from_value(value?, depth - 1))
+                    .collect::<LuaResult<_>>()?,
+            ),
+            LuaValue::Table(table) => Field::Table(named_fields(&table, |_| depth - 1)?),
+            other => Field::Other(other.type_name()),
+        })
+    }
+}
+
+/// The fields of a params table, keyed by name; non-string keys are shown as `[key]`.
+/// Only `operators`, a list of tables, and `wavetable`, a list of numbers, are read
+/// into; any other table value is checked by type alone.
+pub fn fields(table: &LuaTable) -> LuaResult<Vec<(String, Field)>> {
+    named_fields(table, |key| match key {
+        "operators" => 2,
+        "wavetable" => 1,
+        _ => 0,
+    })
+}
+
+fn named_fields(
+    table: &LuaTable,
+    depth: impl Fn(&str) -> usize,
+) -> LuaResult<Vec<(String, Field)>> {
+    table
+        .pairs::<LuaValue, LuaValue>()
+        .map(|pair| {
+            let (key, value) = pair?;
+            let name = match key {
+                LuaValue::String(s) => s.to_str()?.to_string(),
+                LuaValue::Integer(n) => format!("[{}]", n),
+                other => format!("[{}]", other.type_name()),
+            };
+            let depth = depth(&name);
+            Ok((name, Field::from_value(value, depth)?))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    Unknown {
+        field: String,
+        suggestion: Option<&'static str>,
+    },
+    Type {
+        field: String,
+        expected: &'static str,
+        got: &'static str,
+    },
+    Clamped {
+        field: String,
+        value: f64,
+        clamped: f64,
+    },
+    Invalid {
+        field: String,
+        reason: String,
+    },
+}
+
+impl Issue {
+    pub fn field(&self) -> &str {
+        match self {
+            Issue::Unknown { field, .. }
+            | Issue::Type { field, .. }
+            | Issue::Clamped { field, .. }
+            | Issue::Invalid { field, .. } => field,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Issue::Unknown {
+                field,
+                suggestion: Some(suggestion),
+            } => format!("Unknown field '{}', did you mean '{}'?", field, suggestion),
+            Issue::Unknown { field, .. } => format!("Unknown field '{}'", field),
+            Issue::Type {
+                field,
+                expected,
+                got,
+            } => format!(
+                "Invalid type for {}: expected {}, got {}",
+                field, expected, got
+            ),
+            Issue::Clamped {
+                field,
+                value,
+                clamped,
+            } => format!(
+                "{} = {} is out of range, clamped to {}",
+                field,
+                value,
+                (clamped * 1000.0).round() / 1000.0
+            ),
+            Issue::Invalid { field, reason } => format!("Invalid {}: {}", field, reason),
+        }
+    }
+}
+
+impl IntoLua for Issue {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaValue> {
+        let table = lua.create_table()?;
+        table.set("field", self.field())?;
+        table.set("message", self.message())?;
+        match self {
+            Issue::Unknown { suggestion, .. } => {
+                table.set("kind", "unknown")?;
+                table.set("suggestion", suggestion)?;
+            }
+            Issue::Type { expected, got, .. } => {
+                table.set("kind", "type")?;
+                table.set("expected", expected)?;
+                table.set("got", got)?;
+            }
+            Issue::Clamped { value, clamped, .. } => {
+                table.set("kind", "clamped")?;
+                table.set("value", value)?;
+                table.set("clamped", clamped)?;
+            }
+            Issue::Invalid { .. } => table.set("kind", "invalid")?,
+        }
+        Ok(LuaValue::Table(table))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Number,
+    Integer,
+    /// Hz, or a note name / MIDI note number string.
+    Note,
+    /// One of a set of names, checked by the parser.
+    Name(fn(&str) -> Result<(), String>),
+    Numbers,
+    Operators,
+}
+
+const KEYS: &[(&str, Kind)] = &[
+    ("wave_type", Kind::Integer),
+    ("base_freq", Kind::Note),
+    ("freq_limit", Kind::Note),
+    ("freq_ramp", Kind::Number),
+    ("freq_dramp", Kind::Number),
+    ("duty", Kind::Number),
+    ("duty_ramp", Kind::Number),
+    ("vib_speed", Kind::Number),
+    ("vib_strength", Kind::Number),
+    ("env_attack", Kind::Number),
+    ("env_sustain", Kind::Number),
+    ("env_punch", Kind::Number),
+    ("env_decay", Kind::Number),
+    ("lpf_freq", Kind::Number),
+    ("lpf_ramp", Kind::Number),
+    ("lpf_resonance", Kind::Number),
+    ("hpf_freq", Kind::Number),
+    ("hpf_ramp", Kind::Number),
+    ("pha_offset", Kind::Number),
+    ("pha_ramp", Kind::Number),
+    ("repeat_speed", Kind::Number),
+    ("arp_speed", Kind::Number),
+    ("arp_mod", Kind::Number),
+    ("sample_rate", Kind::Integer),
+    ("sample_size", Kind::Integer),
+    ("sound_vol", Kind::Number),
+    ("volume", Kind::Number),
+    ("glide", Kind::Number),
+    ("engine", Kind::Name(engine)),
+    ("switch", Kind::Name(switch_kind)),
+    ("stroke", Kind::Name(stroke)),
+    ("hold", Kind::Number),
+    ("algorithm", Kind::Name(algorithm)),
+    ("operators", Kind::Operators),
+    ("damping", Kind::Number),
+    ("brightness", Kind::Number),
+    ("pick_position", Kind::Number),
+    ("wavetable", Kind::Numbers),
+    ("channel", Kind::Name(channel)),
+    ("noise_mode", Kind::Name(noise_mode)),
+    ("noise_period", Kind::Integer),
+    ("level", Kind::Integer),
+    ("envelope", Kind::Integer),
+    ("overtones", Kind::Integer),
+    ("overtone_falloff", Kind::Number),
+    ("bit_crush", Kind::Number),
+    ("bit_crush_sweep", Kind::Number),
+    ("compression", Kind::Number),
+];
+
+const OPERATOR_KEYS: [&str; 7] = [
+    "ratio", "index", "attack", "decay", "sustain", "release", "feedback",
+];
+
+fn engine(name: &str) -> Result<(), String> {
+    match name {
+        "sfxr" | "switch" | "fm" | "pluck" | "nes" => Ok(()),
+        _ => Err(format!("unknown engine '{}'", name)),
+    }
+}
+
+fn switch_kind(name: &str) -> Result<(), String> {
+    SwitchKind::parse(name).map(drop)
+}
+
+fn stroke(name: &str) -> Result<(), String> {
+    Stroke::parse(name).map(drop)
+}
+
+fn algorithm(name: &str) -> Result<(), String> {
+    Algorithm::parse(name).map(drop)
+}
+
+fn channel(name: &str) -> Result<(), String> {
+    Channel::parse(name).map(drop)
+}
+
+fn noise_mode(name: &str) -> Result<(), String> {
+    match name {
+        "long" | "short" => Ok(()),
+        _ => Err(format!("unknown noise mode '{}'", name)),
+    }
+}
+
+// Edit distance counting a swap of neighbours as one edit, for suggesting the field a
+// typo was meant to be
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+fn suggest<'a>(field: &str, known: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let limit = (field.chars().count() / 3).clamp(1, 3);
+    known
+        .map(|key| (distance(field, key), key))
+        .filter(|(d, _)| *d <= limit)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, key)| key)
+}
+
+// The value `from_table` ends up using for `value`; sfxr parameters go through their
+// normalised form and back
+fn effective(key: &str, value: f64, sfxr: bool) -> f64 {
+    let through = |to: fn(f64) -> f64, from: fn(f64) -> f64| from(to(value));
+    match key {
+        "sound_vol" | "volume" | "overtone_falloff" | "bit_crush" | "compression" | "damping"
+        | "brightness" | "pick_position" => value.clamp(0.0, 1.0),
+        "overtones" => value.clamp(0.0, 10.0),
+        "level" | "envelope" | "noise_period" => value.clamp(0.0, 15.0),
+        "hold" | "glide" => value.max(0.0),
+        // 0 switches most effects off rather than being a value in their range
+        _ if !sfxr || value == 0.0 => value,
+        "env_attack" | "env_sustain" | "env_decay" => {
+            env_to_seconds(seconds_to_env(value as f32)) as f64
+        }
+        "base_freq" => through(freq_to_param, param_to_freq),
+        "freq_limit" if freq_to_param(value) == 0.0 => 0.0,
+        "freq_limit" => through(freq_to_param, param_to_freq),
+        "env_punch" => through(punch_to_param, param_to_punch),
+        "freq_ramp" => through(freq_ramp_to_param, param_to_freq_ramp),
+        "freq_dramp" => through(freq_dramp_to_param, param_to_freq_dramp),
+        "vib_speed" => through(vib_speed_to_param, param_to_vib_speed),
+        "vib_strength" => through(vib_strength_to_param, param_to_vib_strength),
+        "arp_mod" => through(arp_mod_to_param, param_to_arp_mod),
+        "arp_speed" => through(arp_speed_to_param, param_to_arp_speed),
+        "repeat_speed" => through(repeat_speed_to_param, param_to_repeat_speed),
+        "duty" => through(duty_to_param, param_to_duty),
+        "duty_ramp" => through(duty_ramp_to_param, param_to_duty_ramp),
+        "pha_offset" => through(pha_offset_to_param, param_to_pha_offset),
+        "pha_ramp" => through(pha_ramp_to_param, param_to_pha_ramp),
+        "lpf_freq" => through(lpf_freq_to_param, param_to_lpf_freq),
+        "lpf_ramp" => through(lpf_ramp_to_param, param_to_lpf_ramp),
+        "lpf_resonance" => through(lpf_resonance_to_param, param_to_lpf_resonance),
+        "hpf_freq" => through(hpf_freq_to_param, param_to_hpf_freq),
+        "hpf_ramp" => through(hpf_ramp_to_param, param_to_hpf_ramp),
+        _ => value,
+    }
+}
+
+fn check_number(issues: &mut Vec<Issue>, key: &str, value: f64, sfxr: bool) {
+    let clamped = effective(key, value, sfxr);
+    // Loose enough for the f32 round trip of the envelope
+    if (clamped - value).abs() > 1e-4 * value.abs().max(1.0) {
+        issues.push(Issue::Clamped {
+            field: key.to_string(),
+            value,
+            clamped,
+        });
+    }
+}
+
+fn check_operators(issues: &mut Vec<Issue>, field: &Field) {
+    let operators = match field {
+        Field::List(operators) => operators,
+        other => {
+            issues.push(Issue::Type {
+                field: "operators".into(),
+                expected: "list of tables",
+                got: other.type_name(),
+            });
+            return;
+        }
+    };
+    if !(2..=4).contains(&operators.len()) {
+        issues.push(Issue::Invalid {
+            field: "operators".into(),
+            reason: format!("FM voices need 2 to 4 operators, got {}", operators.len()),
+        });
+    }
+    for (i, operator) in operators.iter().enumerate() {
+        let prefix = format!("operators[{}]", i + 1);
+        let entries = match operator {
+            Field::Table(entries) => entries,
+            other => {
+                issues.push(Issue::Type {
+                    field: prefix,
+                    expected: "table",
+                    got: other.type_name(),
+                });
+                continue;
+            }
+        };
+        for (key, value) in entries {
+            let field = format!("{}.{}", prefix, key);
+            if !OPERATOR_KEYS.contains(&key.as_str()) {
+                issues.push(Issue::Unknown {
+                    field,
+                    suggestion: suggest(key, OPERATOR_KEYS.into_iter()),
+                });
+            } else if !matches!(value, Field::Number(_)) {
+                issues.push(Issue::Type {
+                    field,
+                    expected: "number",
+                    got: value.type_name(),
+                });
+            }
+        }
+    }
+}
+
+/// Every problem with a params table, sorted by field.
+pub fn check(fields: &[(String, Field)]) -> Vec<Issue> {
+    let sfxr = fields
+        .iter()
+        .find(|(key, _)| key == "engine")
+        .is_none_or(|(_, value)| *value == Field::Text("sfxr".into()));
+    let mut issues = Vec::new();
+
+    for (key, value) in fields {
+        let Some((_, kind)) = KEYS.iter().find(|(name, _)| *name == key.as_str()) else {
+            issues.push(Issue::Unknown {
+                field: key.clone(),
+                suggestion: suggest(key, KEYS.iter().map(|(name, _)| *name)),
+            });
+            continue;
+        };
+        let mismatch = |expected| Issue::Type {
+            field: key.clone(),
+            expected,
+            got: value.type_name(),
+        };
+
+        match (kind, value) {
+            (Kind::Number, Field::Number(n)) => check_number(&mut issues, key, *n, sfxr),
+            (Kind::Integer, Field::Number(n)) if n.fract() != 0.0 => {
+                issues.push(Issue::Type {
+                    field: key.clone(),
+                    expected: "integer",
+                    got: "number",
+                });
+            }
+            (Kind::Integer, Field::Number(n)) if key == "wave_type" => {
+                if let Err(reason) = Waveform::from_index(*n as i64) {
+                    issues.push(Issue::Invalid {
+                        field: key.clone(),
+                        reason,
+                    });
+                }
+            }
+            (Kind::Integer, Field::Number(n)) => check_number(&mut issues, key, *n, sfxr),
+            (Kind::Note, Field::Number(n)) => check_number(&mut issues, key, *n, sfxr),
+            (Kind::Note, Field::Text(note)) => {
+                if let Err(reason) = pitch::parse_note(note) {
+                    issues.push(Issue::Invalid {
+                        field: key.clone(),
+                        reason,
+                    });
+                }
+            }
+            (Kind::Name(parse), Field::Text(name)) => {
+                if let Err(reason) = parse(name) {
+                    issues.push(Issue::Invalid {
+                        field: key.clone(),
+                        reason,
+                    });
+                }
+            }
+            (Kind::Numbers, Field::List(values)) => {
+                if values.len() < 2 {
+                    issues.push(Issue::Invalid {
+                        field: key.clone(),
+                        reason: "expected at least 2 values".into(),
+                    });
+                }
+                for (i, value) in values.iter().enumerate() {
+                    if !matches!(value, Field::Number(_)) {
+                        issues.push(Issue::Type {
+                            field: format!("{}[{}]", key, i + 1),
+                            expected: "number",
+                            got: value.type_name(),
+                        });
+                    }
+                }
+            }
+            (Kind::Operators, field) => check_operators(&mut issues, field),
+            (Kind::Number | Kind::Integer, _) => issues.push(mismatch("number")),
+            (Kind::Note, _) => issues.push(mismatch("number or note name")),
+            (Kind::Name(_), _) => issues.push(mismatch("string")),
+            (Kind::Numbers, _) => issues.push(mismatch("list of numbers")),
+        }
+    }
+
+    issues.sort_by(|a, b| a.field().cmp(b.field()));
+    issues
+}
@@ -0,0 +1,160 @@
+// Synthesis engines besides sfxr, selected with `engine = "..."` in a params table.
+// Voices render at unit gain; SoundParams applies the volume, transpose and time scale.
+
+use crate::fm::FmVoice;
+use crate::nes::NesVoice;
+use crate::pluck::PluckVoice;
+use crate::switch::SwitchVoice;
+use crate::tuning::Tuning;
+use mlua::prelude::*;
+
+/// Longest note hold or envelope stage, in seconds. Voices allocate their whole
+/// duration up front, so this bounds what a single sound can take.
+pub const MAX_SECONDS: f64 = 60.0;
+
+/// Clamp a duration into `0..=MAX_SECONDS`, NaN to 0.
+pub fn clamp_seconds(seconds: f64) -> f64 {
+    if seconds.is_nan() {
+        return 0.0;
+    }
+    seconds.clamp(0.0, MAX_SECONDS)
+}
+
+pub trait Voice {
+    /// Pitch in Hz the voice is tuned to.
+    fn freq(&self) -> f64;
+    fn set_freq(&mut self, hz: f64);
+    /// Hold the note for `seconds` before it is released.
+    fn set_note_length(&mut self, seconds: f32);
+    /// Multiply every duration by `factor`.
+    fn scale_time(&mut self, factor: f64);
+    fn duration_samples(&self) -> usize;
+    fn render(&self) -> Vec<f32>;
+    /// Render with the pitch bent by `glide`. Voices that can't bend ignore it.
+    fn render_glide(&self, _glide: &Glide) -> Vec<f32> {
+        self.render()
+    }
+}
+
+/// A bend into a note's own pitch, linear in semitones.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Glide {
+    /// Starting frequency as a multiple of the note's.
+    from_ratio: f64,
+    samples: usize,
+}
+
+impl Glide {
+    pub const NONE: Glide = Glide {
+        from_ratio: 1.0,
+        samples: 0,
+    };
+
+    /// Bend from `from_hz` to `to_hz` over `samples`.
+    pub fn new(from_hz: f64, to_hz: f64, samples: usize) -> Glide {
+        if from_hz <= 0.0 || to_hz <= 0.0 || !(from_hz / to_hz).is_finite() {
+            return Glide::NONE;
+        }
+        Glide {
+            from_ratio: from_hz / to_hz,
+            samples,
+        }
+    }
+
+    /// Frequency multiplier at sample `i`.
+    pub fn ratio(&self, i: usize) -> f64 {
+        if i >= self.samples {
+            return 1.0;
+        }
+        self.from_ratio.powf(1.0 - i as f64 / self.samples as f64)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Engine {
+    Switch(SwitchVoice),
+    Fm(FmVoice),
+    Pluck(PluckVoice),
+    Nes(NesVoice),
+}
+
+impl Engine {
+    pub fn voice(&self) -> &dyn Voice {
+        match self {
+            Engine::Switch(voice) => voice,
+            Engine::Fm(voice) => voice,
+            Engine::Pluck(voice) => voice,
+            Engine::Nes(voice) => voice,
+        }
+    }
+
+    pub fn voice_mut(&mut self) -> &mut dyn Voice {
+        match self {
+            Engine::Switch(voice) => voice,
+            Engine::Fm(voice) => voice,
+            Engine::Pluck(voice) => voice,
+            Engine::Nes(voice) => voice,
+        }
+    }
+
+    pub fn from_table(name: &str, table: &LuaTable, tuning: &Tuning) -> LuaResult<Engine> {
+        match name {
+            "switch" => Ok(Engine::Switch(SwitchVoice::from_table(table, tuning)?)),
+            "fm" => Ok(Engine::Fm(FmVoice::from_table(table, tuning)?)),
+            "pluck" => Ok(Engine::Pluck(PluckVoice::from_table(table, tuning)?)),
+            "nes" => Ok(Engine::Nes(NesVoice::from_table(table, tuning)?)),
+            _ => Err(mlua::Error::RuntimeError(format!(
+                "Unknown engine: {}",
+                name
+            ))),
+        }
+    }
+
+    /// Write `engine` and the voice's keys into `table`.
+    pub fn to_table(&self, lua: &Lua, table: &LuaTable) -> LuaResult<()> {
+        match self {
+            Engine::Switch(voice) => {
+                table.set("engine", "switch")?;
+                voice.to_table(table)
+            }
+            Engine::Fm(voice) => {
+                table.set("engine", "fm")?;
+                voice.to_table(lua, table)
+            }
+            Engine::Pluck(voice) => {
+                table.set("engine", "pluck")?;
+                voice.to_table(table)
+            }
+            Engine::Nes(voice) => {
+                table.set("engine", "nes")?;
+                voice.to_table(table)
+            }
+        }
+    }
+}
+
+// xorshift32 noise in [-1, 1], seeded so a voice always renders the same way
+pub struct Noise(u32);
+
+impl Noise {
+    pub fn new(seed: u32) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub fn sample(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+/// Scale `buffer` so its loudest sample reaches `peak`.
+pub fn normalize(buffer: &mut [f32], peak: f32) {
+    let max = buffer.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+    if max > 0.0 {
+        for sample in buffer.iter_mut() {
+            *sample *= peak / max;
+        }
+    }
+}
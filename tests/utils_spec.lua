@@ -414,4 +414,375 @@ describe("Utils", function()
                 "sound_vol should be nil when not in JSON and master_volume is nil")
         end)
     end)
+
+    describe("timeline", function()
+        local Lib = require("player-one.binary")
+        local captured
+        local original_append_timeline
+        local original_min_interval
+
+        before_each(function()
+            original_append_timeline = Lib.append_timeline
+            original_min_interval = Config.min_interval
+            Config.min_interval = 0
+            Lib.append_timeline = function(entries)
+                captured = entries
+            end
+            captured = nil
+        end)
+
+        after_each(function()
+            Lib.append_timeline = original_append_timeline
+            Config.min_interval = original_min_interval
+        end)
+
+        it("should send a sequence to the binary in a single call", function()
+            Utils.append({
+                { wave_type = 1, base_freq = 523.25 },
+                { wave_type = 1, base_freq = 659.25 },
+            })
+
+            assert.is_not_nil(captured)
+            assert.are.equal(2, #captured)
+            assert.are.equal(659.25, captured[2].base_freq)
+        end)
+
+        it("should keep timing and gain of timeline entries", function()
+            Utils.append_timeline({
+                { sound = { wave_type = 1, base_freq = 440 }, at_ms = 0 },
+                { sound = { wave_type = 1, base_freq = 554.37 }, at_ms = 0, gain = 0.5 },
+            })
+
+            assert.are.equal(0, captured[2].at_ms)
+            assert.are.equal(0.5, captured[2].gain)
+            assert.are.equal(554.37, captured[2].sound.base_freq)
+        end)
+
+        it("should reject invalid timeline entries", function()
+            local ok = pcall(Utils.append_timeline, { { sound = { wave_type = 1 }, at_ms = "soon" } })
+            assert.is_false(ok)
+        end)
+    end)
+
+    describe("engines", function()
+        local Lib = require("player-one.binary")
+        local captured
+        local original_play
+        local original_min_interval
+
+        before_each(function()
+            original_play = Lib.play
+            original_min_interval = Config.min_interval
+            Config.min_interval = 0
+            Lib.play = function(params)
+                captured = params
+            end
+            captured = nil
+        end)
+
+        after_each(function()
+            Lib.play = original_play
+            Config.min_interval = original_min_interval
+        end)
+
+        it("should pass engine settings through", function()
+            Utils.play({ engine = "switch", switch = "clicky", stroke = "pair", hold = 0.05 })
+
+            assert.are.equal("switch", captured.engine)
+            assert.are.equal("clicky", captured.switch)
+            assert.are.equal("pair", captured.stroke)
+            assert.are.equal(0.05, captured.hold)
+        end)
+
+        it("should reject non-string engine names", function()
+            local ok = pcall(Utils.play, { engine = 1 })
+            assert.is_false(ok)
+        end)
+
+        it("should pass bfxr strings through", function()
+            local original_unsupported = Lib.bfxr_unsupported
+            local original_master_volume = Config.master_volume
+            Lib.bfxr_unsupported = function()
+                return {}
+            end
+            Config.master_volume = nil
+
+            Utils.play("8, 0.5, 0, 0.2, 0.1, 0.3")
+
+            Lib.bfxr_unsupported = original_unsupported
+            Config.master_volume = original_master_volume
+            assert.are.equal("8,0.5,0,0.2,0.1,0.3", captured)
+        end)
+
+        it("should reject bfxr strings with non-number values", function()
+            local ok = pcall(Utils.play, "8,loud,0")
+            assert.is_false(ok)
+        end)
+
+        it("should decode share links through JSON", function()
+            local original_to_json = Lib.share_link_to_json
+            local original_master_volume = Config.master_volume
+            local received
+            Lib.share_link_to_json = function(link)
+                received = link
+                return '{"wave_type":1,"sound_vol":0.5}'
+            end
+            Config.master_volume = 0.5
+
+            Utils.play("https://sfxr.me/#7BMHBGHKKnn7bgcHQBFNLs7GZ")
+
+            Lib.share_link_to_json = original_to_json
+            Config.master_volume = original_master_volume
+            assert.are.equal("https://sfxr.me/#7BMHBGHKKnn7bgcHQBFNLs7GZ", received)
+            assert.are.equal(0.25, vim.json.decode(captured).sound_vol)
+        end)
+
+        it("should pass FM operators through", function()
+            Utils.play({
+                engine = "fm",
+                algorithm = "pairs",
+                operators = { { ratio = 1 }, { ratio = 3.5, index = 2, decay = 0.1 } },
+            })
+
+            assert.are.equal("pairs", captured.algorithm)
+            assert.are.equal(2, #captured.operators)
+            assert.are.equal(3.5, captured.operators[2].ratio)
+        end)
+
+        it("should pass wavetables through", function()
+            Utils.play({ wavetable = { 0, 4, 8, 15, 8, 4 } })
+
+            assert.are.same({ 0, 4, 8, 15, 8, 4 }, captured.wavetable)
+        end)
+
+        it("should reject wavetables with non-number values", function()
+            local ok = pcall(Utils.play, { wavetable = { 0, "high" } })
+            assert.is_false(ok)
+        end)
+
+        it("should reject FM operators with non-number fields", function()
+            local ok = pcall(Utils.play, { engine = "fm", operators = { { ratio = "2" } } })
+            assert.is_false(ok)
+        end)
+    end)
+
+    describe("export", function()
+        local Lib = require("player-one.binary")
+        local original_master_volume
+
+        before_each(function()
+            original_master_volume = Config.master_volume
+        end)
+
+        after_each(function()
+            Config.master_volume = original_master_volume
+        end)
+
+        it("should save .sfs files without the master volume", function()
+            local original_save_sfs = Lib.save_sfs
+            local saved_path, saved_params
+            Lib.save_sfs = function(path, params)
+                saved_path = path
+                saved_params = params
+            end
+            Config.master_volume = 0.5
+
+            Utils.save_sfs("/tmp/laser.sfs", { wave_type = 1, volume = 0.8 })
+
+            Lib.save_sfs = original_save_sfs
+            assert.are.equal("/tmp/laser.sfs", saved_path)
+            assert.are.equal(0.8, saved_params.sound_vol)
+            assert.are.equal(0.5, Config.master_volume)
+        end)
+
+        it("should export JSON without the master volume", function()
+            local original_to_json = Lib.to_json
+            local exported
+            Lib.to_json = function(params)
+                exported = params
+                return "{}"
+            end
+            Config.master_volume = 0.5
+
+            local json = Utils.to_json('{"wave_type":2,"sound_vol":0.4}')
+
+            Lib.to_json = original_to_json
+            assert.are.equal("{}", json)
+            assert.are.equal(0.4, vim.json.decode(exported).sound_vol)
+        end)
+
+        it("should describe sounds without the master volume", function()
+            local original_describe = Lib.describe
+            Lib.describe = function(params)
+                return params
+            end
+            Config.master_volume = 0.5
+
+            local described = Utils.describe({ wave_type = 2, base_freq = 440, volume = 0.6 })
+
+            Lib.describe = original_describe
+            assert.are.equal(440, described.base_freq)
+            assert.are.equal(0.6, described.sound_vol)
+        end)
+
+        it("should read slides and arpeggio speeds from tables", function()
+            local described = Utils.describe({ wave_type = 0, freq_ramp = 1, arp_speed = 0.0005 })
+
+            -- One octave per second up, not the full downward slide
+            assert.is_true(math.abs(described.freq_ramp - 1) < 1e-3)
+            -- Arpeggios shorter than 32 samples jump at the shortest period
+            assert.is_true(math.abs(described.arp_speed - 32 / 44100) < 1e-6)
+        end)
+
+        it("should reject invalid preset seeds", function()
+            local ok = pcall(Utils.preset_to_json, "laser", 1.5)
+            assert.is_false(ok)
+        end)
+
+        it("should restore the master volume when a sound is invalid", function()
+            Config.master_volume = 0.5
+
+            local ok = pcall(Utils.to_share_link, { wave_type = "square" })
+
+            assert.is_false(ok)
+            assert.are.equal(0.5, Config.master_volume)
+        end)
+    end)
+
+    describe("strict", function()
+        local Lib = require("player-one.binary")
+        local original_play, original_validate, original_strict, original_min_interval
+        local validated
+
+        before_each(function()
+            original_play = Lib.play
+            original_validate = Lib.validate
+            original_strict = Config.strict
+            original_min_interval = Config.min_interval
+            Config.min_interval = 0
+            Lib.play = function() end
+            Lib.validate = function(params)
+                validated = params
+                if params.base_frq then
+                    return {
+                        {
+                            kind = "unknown",
+                            field = "base_frq",
+                            suggestion = "base_freq",
+                            message = "Unknown field 'base_frq', did you mean 'base_freq'?",
+                        },
+                    }
+                end
+                return {}
+            end
+            validated = nil
+        end)
+
+        after_each(function()
+            Lib.play = original_play
+            Lib.validate = original_validate
+            Config.strict = original_strict
+            Config.min_interval = original_min_interval
+        end)
+
+        it("should raise the issues as a table in strict mode", function()
+            Config.strict = true
+
+            local ok, err = pcall(Utils.play, { base_frq = 440 })
+
+            assert.is_false(ok)
+            assert.are.equal("table", type(err))
+            assert.are.equal("base_freq", err[1].suggestion)
+            assert.are.equal("Unknown field 'base_frq', did you mean 'base_freq'?", tostring(err))
+        end)
+
+        it("should play valid sounds in strict mode", function()
+            Config.strict = true
+
+            local ok = pcall(Utils.play, { base_freq = 440 })
+
+            assert.is_true(ok)
+            assert.are.equal(440, validated.base_freq)
+        end)
+
+        it("should not validate outside strict mode", function()
+            Config.strict = false
+
+            Utils.play({ base_frq = 440 })
+
+            assert.is_nil(validated)
+        end)
+
+        it("should validate theme sounds when the theme loads", function()
+            Config.strict = true
+
+            local ok, err = pcall(Utils.load_theme, {
+                { event = "InsertEnter", sound = { { base_freq = 440 }, { base_frq = 440 } } },
+            })
+
+            assert.is_false(ok)
+            assert.are.equal("table", type(err))
+            assert.are.equal("base_freq", err[1].suggestion)
+        end)
+
+        it("should validate cyclic tables", function()
+            local M = { base_freq = 440 }
+            M.__index = M
+            local operator = { ratio = 2 }
+            operator.self = operator
+
+            local issues = original_validate(M)
+            local fm_issues = original_validate({ engine = "fm", operators = { operator, operator } })
+
+            assert.are.equal(1, #issues)
+            assert.are.equal("__index", issues[1].field)
+            assert.are.equal(2, #fm_issues)
+            assert.are.equal("operators[1].self", fm_issues[1].field)
+        end)
+    end)
+
+    describe("typed_key", function()
+        local buf, other
+
+        before_each(function()
+            buf = vim.api.nvim_create_buf(false, true)
+            other = vim.api.nvim_create_buf(false, true)
+            vim.api.nvim_set_current_buf(buf)
+            vim.api.nvim_buf_set_lines(buf, 0, -1, false, { "ab" })
+            vim.api.nvim_win_set_cursor(0, { 1, 1 })
+            Utils.typed_key()
+        end)
+
+        after_each(function()
+            vim.api.nvim_buf_delete(buf, { force = true })
+            vim.api.nvim_buf_delete(other, { force = true })
+        end)
+
+        it("should detect a new line as <CR>", function()
+            vim.api.nvim_buf_set_lines(buf, 0, -1, false, { "ab", "" })
+            vim.api.nvim_win_set_cursor(0, { 2, 0 })
+
+            assert.are.equal("<CR>", Utils.typed_key())
+        end)
+
+        it("should detect a shorter line as <BS>", function()
+            vim.api.nvim_buf_set_lines(buf, 0, -1, false, { "a" })
+            vim.api.nvim_win_set_cursor(0, { 1, 0 })
+
+            assert.are.equal("<BS>", Utils.typed_key())
+        end)
+
+        it("should keep the state per buffer", function()
+            vim.api.nvim_set_current_buf(other)
+            vim.api.nvim_buf_set_lines(other, 0, -1, false, { "x", "yz" })
+            vim.api.nvim_win_set_cursor(0, { 2, 1 })
+            assert.are.equal("y", Utils.typed_key())
+
+            -- Back in the first buffer, the other buffer's rows don't count as <BS>
+            vim.api.nvim_set_current_buf(buf)
+            vim.api.nvim_buf_set_lines(buf, 0, -1, false, { "abc" })
+            vim.api.nvim_win_set_cursor(0, { 1, 2 })
+            assert.are.equal("b", Utils.typed_key())
+        end)
+    end)
 end)